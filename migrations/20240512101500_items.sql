@@ -0,0 +1,13 @@
+CREATE TABLE items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    feed_id INTEGER NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+    guid TEXT NOT NULL,
+    link TEXT,
+    title TEXT,
+    description TEXT,
+    pub_date TEXT,
+    first_seen TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (feed_id, guid)
+);
+
+CREATE INDEX items_feed_id_pub_date ON items (feed_id, pub_date);
@@ -0,0 +1,5 @@
+ALTER TABLE feeds ADD COLUMN refresh_interval INTEGER NOT NULL DEFAULT 3600;
+ALTER TABLE feeds ADD COLUMN last_fetched TEXT;
+ALTER TABLE feeds ADD COLUMN last_success TEXT;
+ALTER TABLE feeds ADD COLUMN last_error TEXT;
+ALTER TABLE feeds ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
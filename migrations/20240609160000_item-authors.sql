@@ -0,0 +1 @@
+ALTER TABLE items ADD COLUMN author TEXT;
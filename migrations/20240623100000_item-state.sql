@@ -0,0 +1,3 @@
+ALTER TABLE items ADD COLUMN read INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE items ADD COLUMN starred INTEGER NOT NULL DEFAULT 0;
+CREATE INDEX items_unread ON items (feed_id) WHERE read = 0;
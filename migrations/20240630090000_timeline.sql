@@ -0,0 +1,5 @@
+-- first_seen is SQLite's CURRENT_TIMESTAMP, bring it into the RFC 3339 format of pub_date
+ALTER TABLE items ADD COLUMN sort_date TEXT
+    GENERATED ALWAYS AS (COALESCE(pub_date, strftime('%Y-%m-%dT%H:%M:%S+00:00', first_seen))) VIRTUAL;
+
+CREATE INDEX items_sort_date ON items (sort_date DESC, id DESC);
@@ -0,0 +1,16 @@
+ALTER TABLE articles ADD COLUMN text TEXT NOT NULL DEFAULT '';
+
+-- Both indexes are written by the application, which strips markup first
+CREATE VIRTUAL TABLE items_fts USING fts5(title, description);
+CREATE VIRTUAL TABLE articles_fts USING fts5(url UNINDEXED, title, text);
+
+-- Deleting a feed cascades to its items, so the index has to follow
+CREATE TRIGGER items_fts_delete AFTER DELETE ON items BEGIN
+    DELETE FROM items_fts WHERE rowid = old.id;
+END;
+
+-- Existing items keep their markup in the index until their feed is refreshed
+INSERT INTO items_fts (rowid, title, description)
+    SELECT id, COALESCE(title, ''), COALESCE(description, '') FROM items;
+INSERT INTO articles_fts (url, title, text)
+    SELECT url, title, content FROM articles;
@@ -0,0 +1,3 @@
+-- User-defined title, shown instead of the upstream title when set
+ALTER TABLE feeds ADD COLUMN custom_title TEXT;
+ALTER TABLE feeds ADD COLUMN notes TEXT NOT NULL DEFAULT '';
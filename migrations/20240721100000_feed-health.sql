@@ -0,0 +1,5 @@
+-- Target of a permanent redirect and how many fetches in a row it has been seen
+ALTER TABLE feeds ADD COLUMN redirect_url TEXT;
+ALTER TABLE feeds ADD COLUMN redirect_count INTEGER NOT NULL DEFAULT 0;
+-- Gone (410) or failing for too long, the scheduler leaves these alone
+ALTER TABLE feeds ADD COLUMN dead INTEGER NOT NULL DEFAULT 0;
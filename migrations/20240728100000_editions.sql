@@ -0,0 +1,6 @@
+-- Every edition that was built, its id is the edition number in the masthead
+CREATE TABLE editions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    built_at TEXT NOT NULL,
+    feed_ids TEXT NOT NULL
+);
@@ -0,0 +1,20 @@
+-- Page geometry of the printers and e-readers documents are made for.
+-- The built-in profiles can't be deleted.
+CREATE TABLE profiles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    page_width_mm REAL NOT NULL,
+    page_height_mm REAL NOT NULL,
+    margin_mm REAL NOT NULL,
+    font_size_pt REAL NOT NULL,
+    columns INTEGER NOT NULL DEFAULT 1,
+    -- 'keep' or 'remove'
+    images TEXT NOT NULL DEFAULT 'keep',
+    builtin INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO profiles (name, page_width_mm, page_height_mm, margin_mm, font_size_pt, columns, images, builtin) VALUES
+    ('a4', 210, 297, 20, 11, 1, 'keep', 1),
+    ('letter', 215.9, 279.4, 19, 11, 1, 'keep', 1),
+    ('kindle', 90, 122, 4, 9, 1, 'keep', 1),
+    ('remarkable', 157, 210, 10, 11, 1, 'keep', 1);
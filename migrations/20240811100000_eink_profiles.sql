@@ -0,0 +1,6 @@
+-- Resolution images are scaled to, the screen resolution for e-readers.
+-- Images are now one of 'keep', 'grayscale', 'dither' or 'remove'.
+ALTER TABLE profiles ADD COLUMN dpi REAL NOT NULL DEFAULT 150;
+
+UPDATE profiles SET dpi = 300, images = 'dither' WHERE name = 'kindle';
+UPDATE profiles SET dpi = 226, images = 'dither' WHERE name = 'remarkable';
@@ -1,6 +1,10 @@
 use crate::error_template::{AppError, ErrorTemplate};
-use crate::feeds::{FeedListView, FeedDetailView};
+use crate::feeds::{FeedListView, FeedDetailView, StarredView};
 use crate::article::ArticleView;
+use crate::edition::EditionView;
+use crate::timeline::TimelineView;
+use crate::search::SearchView;
+use crate::profiles::ProfilesView;
 use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
@@ -24,7 +28,12 @@ pub fn App() -> impl IntoView {
                 <Route path="" view=FeedListView ssr=SsrMode::Async />
                 <Route path="/feeds" view=FeedListView ssr=SsrMode::Async />
                 <Route path="/feeds/:id" view=FeedDetailView ssr=SsrMode::PartiallyBlocked />
+                <Route path="/search" view=SearchView ssr=SsrMode::Async />
+                <Route path="/timeline" view=TimelineView ssr=SsrMode::Async />
+                <Route path="/starred" view=StarredView ssr=SsrMode::Async />
                 <Route path="/article" view=ArticleView ssr=SsrMode::PartiallyBlocked />
+                <Route path="/edition" view=EditionView ssr=SsrMode::Async />
+                <Route path="/profiles" view=ProfilesView ssr=SsrMode::Async />
             </Routes>
         </Router>
     }
@@ -1,104 +1,397 @@
 use crate::layout::Layout;
 use crate::breadcrumbs::{BreadCrumbs, BreadCrumbItem};
-use crate::feeds::get_feed;
+use crate::error_template::ErrorTemplate;
+use crate::errors::FeedError;
+use crate::feeds::{get_feed, MarkItemRead};
 use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "ssr")]
 use axum::{
     http::{StatusCode, Response, header},
     response,
     body::Body,
-    extract::Query,
+    extract::{Extension, Query},
 };
 
 #[cfg(feature = "ssr")]
-fn format_article(article: readability::extractor::Product) -> String {
-    format!("<h1>{}</h1><p class=\"italic\">{}</p>{}", article.title, article.description, article.content)
+use sqlx::SqlitePool;
+
+#[cfg(feature = "ssr")]
+use crate::config::Config;
+
+#[cfg(feature = "ssr")]
+use crate::newspaper::PageLayout;
+
+#[cfg(feature = "ssr")]
+use crate::profiles::Profile;
+
+#[cfg(feature = "ssr")]
+use crate::render::{Document, PdfOptions, Rendered};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct Article {
+    pub url: String,
+    pub title: String,
+    pub description: String,
+    pub content: String,
+    pub fetched_at: String,
+    pub content_hash: String,
+    pub text: String,
 }
 
-#[server]
-pub async fn scrape_article(url: String) -> Result<String, ServerFnError> {
-    use readability::extractor;
-    use tokio::task::spawn_blocking;
+// `content` is the sanitized HTML of the article
+#[cfg(feature = "ssr")]
+fn format_article(article: &Article, content: &str) -> String {
+    use crate::sanitize::escape_text;
 
-    match spawn_blocking(move || {
-        return extractor::scrape(&url)
-    }).await {
-        Ok(article) => match article {
-            Ok(article) => Ok(format_article(article)),
-            Err(err) => Err(ServerFnError::new(format!("Error scraping article: {}", err))),
-        },
-        Err(err) => Err(ServerFnError::new(format!("Error scraping article: {}", err))),
+    format!(
+        "<h1>{}</h1><p class=\"italic\">{}</p>{}",
+        escape_text(&article.title),
+        escape_text(&article.description),
+        content
+    )
+}
+
+// Sanitized HTML of the article with its images embedded, for PDFs and EPUBs
+#[cfg(feature = "ssr")]
+pub(crate) async fn embedded_content(config: &Config, article: &Article, profile: Option<&Profile>, layout: PageLayout) -> String {
+    use crate::images::{embed_images, ImageOptions};
+    use crate::sanitize::sanitize_html;
+
+    let content = sanitize_html(&article.content, config.allow_iframes);
+    if profile.is_some_and(|profile| !profile.keep_images()) {
+        return content;
     }
+    return embed_images(config, &content, &article.url, ImageOptions::new(profile, layout)).await;
+}
+
+#[server]
+pub async fn scrape_article(url: String) -> Result<String, ServerFnError<FeedError>> {
+    use crate::config::use_config;
+    use crate::db::use_pool;
+    use crate::sanitize::sanitize_html;
+
+    let config = use_config()?;
+    let pool = use_pool()?;
+    let article = get_article(&pool, &config, url, false).await?;
+
+    return Ok(format_article(&article, &sanitize_html(&article.content, config.allow_iframes)));
+}
+
+#[server]
+pub async fn rescrape_article(url: String) -> Result<(), ServerFnError<FeedError>> {
+    use crate::config::use_config;
+    use crate::db::use_pool;
+
+    let config = use_config()?;
+    let pool = use_pool()?;
+    get_article(&pool, &config, url, true).await?;
+
+    return Ok(());
 }
 
 #[cfg(feature = "ssr")]
 #[derive(Deserialize)]
 pub struct ArticlePdfQuery {
     url: String,
+    feed_id: Option<i64>,
+    layout: Option<String>,
+    columns: Option<u8>,
+    profile: Option<String>,
 }
 
+// Cache key for articles: no fragment and no utm_* tracking parameters
 #[cfg(feature = "ssr")]
-pub async fn get_article_pdf(query: Query<ArticlePdfQuery>) -> response::Response {
-    let url = query.url.clone();
-    use std::path::PathBuf;
+fn normalize_url(url: &str) -> String {
+    use url::Url;
 
-    use readability::extractor;
-    use tokio::task::spawn_blocking;
-    use pandoc::{Pandoc, InputKind, InputFormat, OutputFormat, OutputKind, PandocOption};
+    let mut parsed_url = match Url::parse(url.trim()) {
+        Ok(parsed_url) => parsed_url,
+        Err(_) => return url.trim().to_string(),
+    };
+
+    parsed_url.set_fragment(None);
+
+    let query = parsed_url
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_"))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    if query.is_empty() {
+        parsed_url.set_query(None);
+    } else {
+        parsed_url.query_pairs_mut().clear().extend_pairs(query);
+    }
+
+    return parsed_url.to_string();
+}
+
+// Returns the cached article, scraping it first if it isn't cached or `refresh` is set
+#[cfg(feature = "ssr")]
+pub async fn get_article(pool: &SqlitePool, config: &Config, url: String, refresh: bool) -> Result<Article, FeedError> {
+    use sha2::{Digest, Sha256};
+
+    // Re-encoding the query can change which page a server returns, so the normalized
+    // URL is only the cache key and the requested one is fetched
+    let key = normalize_url(&url);
+
+    if !refresh {
+        let cached = sqlx::query_as::<_, Article>("SELECT * FROM articles WHERE url = ?")
+            .bind(&key)
+            .fetch_optional(pool)
+            .await?;
+
+        if let Some(article) = cached {
+            return Ok(article);
+        }
+    }
 
     logging::log!("Scraping article: {}", url);
+    let product = scrape(config, url.trim().to_string()).await?;
 
-    let err_response = |e: String| {
-        logging::error!("{}", e);
-        return Response::builder()
-            .status(StatusCode::INTERNAL_SERVER_ERROR)
-            .header(header::CONTENT_TYPE, "text/plain")
-            .body(Body::from(e))
-            .unwrap()
+    let article = Article {
+        content_hash: format!("{:x}", Sha256::digest(product.content.as_bytes())),
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+        url: key,
+        title: product.title,
+        description: product.description,
+        content: product.content,
+        text: product.text,
     };
 
-    let article = match spawn_blocking(move || {
-        extractor::scrape(&url)
-    }).await.unwrap() {
-        Ok(article) => article,
-        Err(e) => return err_response(format!("Error scraping article: {}", e)),
-    };
+    sqlx::query(
+        "INSERT INTO articles (url, title, description, content, fetched_at, content_hash, text)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (url) DO UPDATE SET
+            title = excluded.title,
+            description = excluded.description,
+            content = excluded.content,
+            fetched_at = excluded.fetched_at,
+            content_hash = excluded.content_hash,
+            text = excluded.text",
+    )
+    .bind(&article.url)
+    .bind(&article.title)
+    .bind(&article.description)
+    .bind(&article.content)
+    .bind(&article.fetched_at)
+    .bind(&article.content_hash)
+    .bind(&article.text)
+    .execute(pool)
+    .await?;
 
-    // Add title to HTML as h1 tag
-    let article_html = format_article(article);
+    index_article(pool, &article).await?;
 
-    let mut pandoc = Pandoc::new();
+    return Ok(article);
+}
 
-    // Convert from HTML to PDF
-    pandoc.set_input_format(InputFormat::Html, Vec::new());
-    pandoc.set_output_format(OutputFormat::Pdf, Vec::new());
+#[cfg(feature = "ssr")]
+async fn index_article(pool: &SqlitePool, article: &Article) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM articles_fts WHERE url = ?")
+        .bind(&article.url)
+        .execute(pool)
+        .await?;
+    sqlx::query("INSERT INTO articles_fts (url, title, text) VALUES (?, ?, ?)")
+        .bind(&article.url)
+        .bind(&article.title)
+        .bind(&article.text)
+        .execute(pool)
+        .await?;
 
-    pandoc.set_input(InputKind::Pipe(article_html));
-    pandoc.set_output(OutputKind::Pipe);
+    return Ok(());
+}
+
+// The page is downloaded with the configured client, readability only extracts
+#[cfg(feature = "ssr")]
+async fn scrape(config: &Config, url: String) -> Result<readability::extractor::Product, FeedError> {
+    use crate::feeds::fetch_text;
+    use readability::extractor;
+    use tokio::task::spawn_blocking;
 
-    pandoc.add_option(PandocOption::PdfEngine(PathBuf::from("xelatex")));
+    let parsed_url = url::Url::parse(&url).map_err(|_| FeedError::InvalidUrl(url.clone()))?;
+    let html = fetch_text(config, url).await?;
 
-    // Execute pandoc
-    let result = pandoc.execute();
-    let pdf_bytes: Vec<u8> = match result {
-        Ok(pandoc::PandocOutput::ToBuffer(buffer)) => buffer.into(),
-        Ok(pandoc::PandocOutput::ToBufferRaw(buffer)) => buffer,
-        Ok(pandoc::PandocOutput::ToFile(_)) => return err_response("Pandoc output to file not supported".to_string()),
-        Err(e) => return err_response(format!("Error converting article to PDF: {}", e)),
-    };
+    match spawn_blocking(move || extractor::extract(&mut html.as_bytes(), &parsed_url)).await {
+        Ok(Ok(article)) => Ok(article),
+        Ok(Err(e)) => Err(FeedError::Extraction(e.to_string())),
+        Err(e) => Err(FeedError::Extraction(e.to_string())),
+    }
+}
 
+#[cfg(feature = "ssr")]
+pub fn error_response(e: String) -> response::Response {
+    logging::error!("{}", e);
+    return Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(Body::from(e))
+        .unwrap()
+}
+
+#[cfg(feature = "ssr")]
+pub fn file_response(rendered: Rendered, filename: &str) -> response::Response {
     return Response::builder()
         .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "application/pdf")
-        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"article.pdf\""))
-        .body(Body::from(pdf_bytes))
+        .header(header::CONTENT_TYPE, rendered.content_type)
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename))
+        .body(Body::from(rendered.bytes))
         .unwrap();
 }
 
+#[cfg(feature = "ssr")]
+pub async fn article_pdf(
+    pool: &SqlitePool,
+    config: &Config,
+    url: String,
+    feed_id: Option<i64>,
+    options: PdfOptions,
+) -> Result<Rendered, String> {
+    use crate::newspaper;
+    use crate::render::{pdf_renderer, render_blocking};
+
+    let metadata = article_metadata(pool, &url, feed_id).await;
+
+    let article = get_article(pool, config, url, false).await.map_err(|e| e.to_string())?;
+    let content = embedded_content(config, &article, options.profile.as_ref(), options.layout).await;
+
+    let document = match options.layout {
+        // Add title to HTML as h1 tag
+        PageLayout::Standard => Document {
+            html: format_article(&article, &content),
+            profile: options.profile,
+            ..Document::default()
+        },
+        PageLayout::Newspaper { .. } => {
+            let byline = [&metadata.author, &metadata.date]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" · ");
+            Document {
+                title: Some(article.title.clone()),
+                html: newspaper::format_article(
+                    1,
+                    &article.title,
+                    &byline,
+                    &article.description,
+                    &article.text,
+                    &content,
+                ),
+                layout: options.layout,
+                profile: options.profile,
+                ..metadata
+            }
+        }
+    };
+
+    return render_blocking(pdf_renderer(config), document).await;
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_article_pdf(
+    Extension(config): Extension<Config>,
+    Extension(pool): Extension<SqlitePool>,
+    query: Query<ArticlePdfQuery>,
+) -> response::Response {
+    let options = match PdfOptions::load(&pool, query.layout.as_deref(), query.columns, query.profile.as_deref()).await {
+        Ok(options) => options,
+        Err(e) => return error_response(e),
+    };
+
+    match article_pdf(&pool, &config, query.url.clone(), query.feed_id, options).await {
+        Ok(pdf) => file_response(pdf, "article.pdf"),
+        Err(e) => error_response(e),
+    }
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+pub struct ArticleEpubQuery {
+    url: String,
+    feed_id: Option<i64>,
+}
+
+// Author, source feed and publish date, as far as the stored feed item knows them
+#[cfg(feature = "ssr")]
+async fn article_metadata(pool: &SqlitePool, url: &str, feed_id: Option<i64>) -> Document {
+    use crate::feeds::{Feed, FeedItem};
+
+    let mut document = Document { source: Some(url.to_string()), ..Document::default() };
+
+    let feed_id = match feed_id {
+        Some(feed_id) => feed_id,
+        None => return document,
+    };
+
+    if let Ok(feed) = sqlx::query_as::<_, Feed>("SELECT * FROM feeds WHERE id = ?")
+        .bind(feed_id)
+        .fetch_one(pool)
+        .await
+    {
+        document.publisher = Some(feed.display_title());
+    }
+
+    if let Ok(item) = sqlx::query_as::<_, FeedItem>("SELECT * FROM items WHERE feed_id = ? AND link = ?")
+        .bind(feed_id)
+        .bind(url)
+        .fetch_one(pool)
+        .await
+    {
+        document.author = item.author;
+        document.date = item.pub_date;
+    }
+
+    return document;
+}
+
+#[cfg(feature = "ssr")]
+pub async fn article_epub(
+    pool: &SqlitePool,
+    config: &Config,
+    url: String,
+    feed_id: Option<i64>,
+) -> Result<Rendered, String> {
+    use crate::render::{epub_renderer, render_blocking};
+
+    let mut document = article_metadata(pool, &url, feed_id).await;
+
+    let article = get_article(pool, config, url, false).await.map_err(|e| e.to_string())?;
+
+    let content = embedded_content(config, &article, None, PageLayout::Standard).await;
+
+    document.title = Some(article.title.clone());
+    document.html = format_article(&article, &content);
+
+    return render_blocking(epub_renderer(), document).await;
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_article_epub(
+    Extension(config): Extension<Config>,
+    Extension(pool): Extension<SqlitePool>,
+    query: Query<ArticleEpubQuery>,
+) -> response::Response {
+    match article_epub(&pool, &config, query.url.clone(), query.feed_id).await {
+        Ok(epub) => file_response(epub, "article.epub"),
+        Err(e) => error_response(e),
+    }
+}
+
+// Link to the article view or a download. The article URL is encoded, so its own query
+// and fragment survive and the item can be found by its link again.
+pub fn article_href(path: &str, url: &str, feed_id: i64) -> String {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("url", url)
+        .append_pair("feed_id", &feed_id.to_string())
+        .finish();
+    return format!("{}?{}", path, query);
+}
+
 #[derive(Clone, Params, PartialEq)]
 pub struct ArticleQuery {
     url: String,
@@ -110,21 +403,40 @@ pub fn ArticleView() -> impl IntoView {
     let query = use_query::<ArticleQuery>();
 
     let url = move || {
-        query.with(|q| q.clone().unwrap().url)
+        query.with(|q| q.as_ref().map(|q| q.url.clone()).unwrap_or_default())
     };
 
     let feed = create_blocking_resource(
-        move || query.get().unwrap().feed_id,
-        |id| async move { get_feed(id).await.unwrap() },
+        move || query.with(|q| q.as_ref().ok().map(|q| q.feed_id)),
+        |id| async move {
+            match id {
+                Some(id) => get_feed(id).await,
+                None => Err(FeedError::NotFound("feed".to_string()).into()),
+            }
+        },
     );
 
+    let rescrape = create_server_action::<RescrapeArticle>();
+
     let article = create_resource(
-        move || url(),
-        |url| async move {
-            scrape_article(url).await.unwrap()
+        move || (url(), rescrape.version().get()),
+        |(url, _)| async move {
+            scrape_article(url).await
         }
     );
 
+    let on_rescrape = move |_| {
+        rescrape.dispatch(RescrapeArticle { url: url() });
+    };
+
+    // Opening an article in the browser marks its feed item as read
+    let mark_read = create_server_action::<MarkItemRead>();
+    create_effect(move |_| {
+        if let Ok(q) = query.get() {
+            mark_read.dispatch(MarkItemRead { feed_id: q.feed_id, link: q.url });
+        }
+    });
+
     view! {
         <Html lang="en" />
         <Meta name="description" content="Article content" />
@@ -133,27 +445,42 @@ pub fn ArticleView() -> impl IntoView {
                 <p>Loading...</p>
             </Layout>
         }>
-            {move || feed.get().map(|feed| { view! {
+            <ErrorBoundary fallback=|errors| view! {
                 <Layout headline="Article".to_string()>
-                    <BreadCrumbs items=vec![
-                        BreadCrumbItem { text: "Home".to_string(), url: "/".to_string() },
-                        BreadCrumbItem { text: feed.title.clone(), url: format!("/feeds/{}", feed.id) },
-                        BreadCrumbItem { text: "Article".to_string(), url: url() },
-                    ] />
-                    <p>
-                        <a download href=format!("/article/pdf?url={}", url())>Download as PDF</a>
-                    </p>
-                    <Suspense fallback=|| view! {
-                        <section class="my-4 p-8 border shadow-lg max-w-[80ch]">
-                            <div class="w-[60ch] h-12 rounded bg-slate-100 animate-pulse" />
-                        </section>
-                    }>
-                        {move || article.get().map(|content| { view! {
-                            <section class="prose my-4 p-8 border shadow-lg max-w-[80ch]" inner_html=content></section>
-                        }})}
-                    </Suspense>
+                    <ErrorTemplate errors />
                 </Layout>
-            }})}
+            }>
+                {move || feed.get().map(|feed| feed.map(|feed| { view! {
+                    <Layout headline="Article".to_string()>
+                        <BreadCrumbs items=vec![
+                            BreadCrumbItem { text: "Home".to_string(), url: "/".to_string() },
+                            BreadCrumbItem { text: feed.display_title(), url: format!("/feeds/{}", feed.id) },
+                            BreadCrumbItem { text: "Article".to_string(), url: article_href("/article", &url(), feed.id) },
+                        ] />
+                        <p>
+                            <a class="mr-2" download href=article_href("/article/pdf", &url(), feed.id)>Download as PDF</a>
+                            <a class="mr-2" download href=format!("{}&layout=newspaper", article_href("/article/pdf", &url(), feed.id))>
+                                Download as newspaper PDF
+                            </a>
+                            <a class="mr-2" download href=article_href("/article/epub", &url(), feed.id)>Download as EPUB</a>
+                            <button class="p-2 rounded bg-slate-100" on:click=on_rescrape disabled=move || rescrape.pending().get()>
+                                Re-scrape
+                            </button>
+                        </p>
+                        <Suspense fallback=|| view! {
+                            <section class="my-4 p-8 border shadow-lg max-w-[80ch]">
+                                <div class="w-[60ch] h-12 rounded bg-slate-100 animate-pulse" />
+                            </section>
+                        }>
+                            <ErrorBoundary fallback=|errors| view! { <ErrorTemplate errors /> }>
+                                {move || article.get().map(|content| content.map(|content| { view! {
+                                    <section class="prose my-4 p-8 border shadow-lg max-w-[80ch]" inner_html=content></section>
+                                }}))}
+                            </ErrorBoundary>
+                        </Suspense>
+                    </Layout>
+                }}))}
+            </ErrorBoundary>
         </Suspense>
     }
 }
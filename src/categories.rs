@@ -0,0 +1,178 @@
+use crate::errors::FeedError;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct Category {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+}
+
+#[server]
+pub async fn get_categories() -> Result<Vec<Category>, ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    let categories = sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY name")
+        .fetch_all(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    return Ok(categories);
+}
+
+// Feeds of a category, including those of its subcategories
+#[cfg(feature = "ssr")]
+pub(crate) async fn category_feed_ids(pool: &sqlx::SqlitePool, category_id: i64) -> Result<Vec<i64>, sqlx::Error> {
+    return sqlx::query_scalar::<_, i64>(
+        "WITH RECURSIVE tree(id) AS (
+            SELECT ?
+            UNION ALL
+            SELECT categories.id FROM categories JOIN tree ON categories.parent_id = tree.id
+        )
+        SELECT DISTINCT feed_id FROM feed_categories WHERE category_id IN (SELECT id FROM tree)",
+    )
+    .bind(category_id)
+    .fetch_all(pool)
+    .await;
+}
+
+// (feed id, category id) pairs
+#[server]
+pub async fn get_feed_categories() -> Result<Vec<(i64, i64)>, ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    let memberships = sqlx::query_as::<_, (i64, i64)>("SELECT feed_id, category_id FROM feed_categories")
+        .fetch_all(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    return Ok(memberships);
+}
+
+#[server]
+pub async fn add_category(name: String) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(FeedError::InvalidInput("Category name must not be empty".to_string()).into());
+    }
+
+    let pool = use_pool()?;
+    sqlx::query("INSERT INTO categories (name) VALUES (?)")
+        .bind(name)
+        .execute(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    return Ok(());
+}
+
+// Feeds stay subscribed, they only lose the category
+#[server]
+pub async fn delete_category(id: i64) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    sqlx::query("DELETE FROM categories WHERE id = ?")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    return Ok(());
+}
+
+#[server]
+pub async fn set_feed_category(feed_id: i64, category_id: i64, member: bool) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    let query = if member {
+        "INSERT OR IGNORE INTO feed_categories (feed_id, category_id) VALUES (?, ?)"
+    } else {
+        "DELETE FROM feed_categories WHERE feed_id = ? AND category_id = ?"
+    };
+    sqlx::query(query)
+        .bind(feed_id)
+        .bind(category_id)
+        .execute(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    return Ok(());
+}
+
+#[server]
+pub async fn refresh_category(id: i64) -> Result<(), ServerFnError<FeedError>> {
+    use crate::config::use_config;
+    use crate::db::use_pool;
+    use crate::feeds::{refresh_feed, Feed};
+
+    let config = use_config()?;
+    let pool = use_pool()?;
+
+    for feed_id in category_feed_ids(&pool, id).await.map_err(FeedError::from)? {
+        let feed = sqlx::query_as::<_, Feed>("SELECT * FROM feeds WHERE id = ?")
+            .bind(feed_id)
+            .fetch_one(&pool)
+            .await
+            .map_err(FeedError::from)?;
+
+        // One failing feed shouldn't stop the others, its error is recorded on the feed
+        if let Err(err) = refresh_feed(&pool, &config, &feed).await {
+            logging::warn!("{} ({})", err, feed.url);
+        }
+    }
+
+    return Ok(());
+}
+
+#[component]
+pub fn FeedCategorySettings(feed_id: i64) -> impl IntoView {
+    let set_category = create_server_action::<SetFeedCategory>();
+
+    let categories = create_resource(
+        || (),
+        |_| async move {
+            let categories = get_categories().await.unwrap_or_default();
+            let memberships = get_feed_categories().await.unwrap_or_default();
+            (categories, memberships)
+        },
+    );
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || categories.get().map(|(categories, memberships)| view! {
+                <div class="text-sm my-2 flex flex-wrap items-center gap-4">
+                    "Categories:"
+                    {categories
+                        .into_iter()
+                        .map(|category| {
+                            let category_id = category.id;
+                            let checked = memberships.contains(&(feed_id, category_id));
+                            view! {
+                                <label class="flex items-center gap-1">
+                                    <input
+                                        type="checkbox"
+                                        checked=checked
+                                        on:change=move |ev| set_category.dispatch(SetFeedCategory {
+                                            feed_id,
+                                            category_id,
+                                            member: event_target_checked(&ev),
+                                        })
+                                    />
+                                    {category.name}
+                                </label>
+                            }
+                        })
+                        .collect_view()}
+                </div>
+            })}
+        </Suspense>
+    }
+}
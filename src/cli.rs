@@ -0,0 +1,255 @@
+use crate::config::Config;
+use crate::edition::DEFAULT_ITEMS_PER_FEED;
+use crate::feeds::AddFeedResult;
+use crate::render::PdfOptions;
+use clap::{Args, Parser, Subcommand};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+/// Turns RSS feeds into a newspaper. Starts the web server when no command is given.
+#[derive(Parser)]
+#[command(version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Manage the feed list
+    Feeds {
+        #[command(subcommand)]
+        command: FeedsCommand,
+    },
+    /// Fetch new items for all feeds, or for a single feed or category
+    Refresh {
+        #[arg(long, conflicts_with = "category")]
+        feed: Option<i64>,
+        #[arg(long)]
+        category: Option<i64>,
+    },
+    /// Render a single article
+    Article {
+        #[command(subcommand)]
+        command: ArticleCommand,
+    },
+    /// Render an edition of the latest articles from several feeds
+    Edition {
+        #[command(subcommand)]
+        command: EditionCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum FeedsCommand {
+    /// Add a feed, or list the feeds a website links to
+    Add { url: String },
+    /// List all feeds
+    List,
+    /// Remove a feed and its items
+    Remove { id: i64 },
+}
+
+#[derive(Subcommand)]
+pub enum ArticleCommand {
+    /// Render an article as PDF
+    Pdf {
+        url: String,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        layout: LayoutArgs,
+    },
+    /// Render an article as EPUB
+    Epub {
+        url: String,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EditionCommand {
+    /// Build an edition, as EPUB if the output file ends in .epub and as PDF otherwise
+    Build {
+        /// Comma separated feed ids
+        #[arg(long, value_delimiter = ',', required_unless_present = "category")]
+        feeds: Vec<i64>,
+        /// Include all feeds of this category
+        #[arg(long)]
+        category: Option<i64>,
+        /// Articles per feed
+        #[arg(long, default_value_t = DEFAULT_ITEMS_PER_FEED)]
+        items: usize,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        layout: LayoutArgs,
+    },
+}
+
+#[derive(Args)]
+pub struct LayoutArgs {
+    /// Page layout of PDFs
+    #[arg(long, value_parser = ["standard", "newspaper"])]
+    layout: Option<String>,
+    /// Columns of the newspaper layout, 2 or 3
+    #[arg(long)]
+    columns: Option<u8>,
+    /// Page size and fonts of PDFs, such as a4, letter, kindle or a profile added in the web interface
+    #[arg(long)]
+    profile: Option<String>,
+}
+
+impl LayoutArgs {
+    async fn load(&self, pool: &SqlitePool) -> Result<PdfOptions, String> {
+        return PdfOptions::load(pool, self.layout.as_deref(), self.columns, self.profile.as_deref()).await;
+    }
+}
+
+// Without an explicit path, files go to the configured output directory
+fn output_path(config: &Config, output: Option<PathBuf>, default_name: String) -> PathBuf {
+    match output {
+        Some(output) => output,
+        None => config.output_dir.join(default_name),
+    }
+}
+
+fn write_output(path: &Path, bytes: Vec<u8>) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating {}: {}", parent.display(), e))?;
+    }
+
+    std::fs::write(path, bytes).map_err(|e| format!("Error writing {}: {}", path.display(), e))?;
+    println!("Wrote {}", path.display());
+    return Ok(());
+}
+
+async fn run_feeds(pool: &SqlitePool, config: &Config, command: FeedsCommand) -> Result<(), String> {
+    use crate::feeds::{list_feeds, remove_feed, subscribe};
+
+    match command {
+        FeedsCommand::Add { url } => match subscribe(pool, config, url.clone()).await.map_err(|e| e.to_string())? {
+            AddFeedResult::Added => {
+                println!("Added {}", url);
+                return Ok(());
+            }
+            AddFeedResult::Candidates(candidates) => {
+                println!("{} is not a feed, but links to:", url);
+                for candidate in candidates {
+                    println!("  {}\t{}", candidate.url, candidate.title);
+                }
+                return Err("No feed added".to_string());
+            }
+        },
+        FeedsCommand::List => {
+            let feeds = list_feeds(pool)
+                .await
+                .map_err(|e| format!("Error loading feeds: {}", e))?;
+            for feed in feeds {
+                println!("{}\t{}\t{}", feed.id, feed.display_title(), feed.url);
+            }
+            return Ok(());
+        }
+        FeedsCommand::Remove { id } => match remove_feed(pool, id).await {
+            Ok(true) => {
+                println!("Removed feed {}", id);
+                return Ok(());
+            }
+            Ok(false) => return Err(format!("No feed with id {}", id)),
+            Err(e) => return Err(format!("Error removing feed {}: {}", id, e)),
+        },
+    }
+}
+
+async fn run_refresh(
+    pool: &SqlitePool,
+    config: &Config,
+    feed_id: Option<i64>,
+    category: Option<i64>,
+) -> Result<(), String> {
+    use crate::categories::category_feed_ids;
+    use crate::feeds::{list_feeds, refresh_feed};
+
+    let feeds = list_feeds(pool)
+        .await
+        .map_err(|e| format!("Error loading feeds: {}", e))?;
+
+    let feeds = match (feed_id, category) {
+        (Some(id), _) => {
+            let feeds = feeds.into_iter().filter(|feed| feed.id == id).collect::<Vec<_>>();
+            if feeds.is_empty() {
+                return Err(format!("No feed with id {}", id));
+            }
+            feeds
+        }
+        (None, Some(category)) => {
+            let ids = category_feed_ids(pool, category)
+                .await
+                .map_err(|e| format!("Error loading category {}: {}", category, e))?;
+            feeds.into_iter().filter(|feed| !feed.dead && ids.contains(&feed.id)).collect()
+        }
+        // Dead feeds only get refreshed when asked for by id
+        (None, None) => feeds.into_iter().filter(|feed| !feed.dead).collect(),
+    };
+
+    // Keep going after a failing feed, but report it in the exit status
+    let mut failed = 0;
+    for feed in &feeds {
+        match refresh_feed(pool, config, feed).await {
+            Ok(()) => println!("Refreshed {}", feed.url),
+            Err(err) => {
+                eprintln!("{} ({})", err, feed.url);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} of {} feeds failed to refresh", failed, feeds.len()));
+    }
+    return Ok(());
+}
+
+async fn run_article(pool: &SqlitePool, config: &Config, command: ArticleCommand) -> Result<(), String> {
+    use crate::article::{article_epub, article_pdf};
+
+    match command {
+        ArticleCommand::Pdf { url, output, layout } => {
+            let pdf = article_pdf(pool, config, url, None, layout.load(pool).await?).await?;
+            write_output(&output_path(config, output, "article.pdf".to_string()), pdf.bytes)
+        }
+        ArticleCommand::Epub { url, output } => {
+            let epub = article_epub(pool, config, url, None).await?;
+            write_output(&output_path(config, output, "article.epub".to_string()), epub.bytes)
+        }
+    }
+}
+
+async fn run_edition(pool: &SqlitePool, config: &Config, command: EditionCommand) -> Result<(), String> {
+    use crate::edition::{edition_epub, edition_pdf, resolve_feed_ids};
+
+    match command {
+        EditionCommand::Build { feeds, category, items, output, layout } => {
+            let feeds = resolve_feed_ids(pool, feeds, category).await?;
+            let default_name = format!("edition-{}.pdf", chrono::Local::now().format("%Y-%m-%d"));
+            let path = output_path(config, output, default_name);
+
+            let rendered = match path.extension().and_then(|extension| extension.to_str()) {
+                Some("epub") => edition_epub(pool, config, &feeds, items).await?,
+                _ => edition_pdf(pool, config, &feeds, items, layout.load(pool).await?).await?,
+            };
+            write_output(&path, rendered.bytes)
+        }
+    }
+}
+
+pub async fn run(pool: &SqlitePool, config: &Config, command: Command) -> Result<(), String> {
+    match command {
+        Command::Feeds { command } => run_feeds(pool, config, command).await,
+        Command::Refresh { feed, category } => run_refresh(pool, config, feed, category).await,
+        Command::Article { command } => run_article(pool, config, command).await,
+        Command::Edition { command } => run_edition(pool, config, command).await,
+    }
+}
@@ -0,0 +1,231 @@
+use crate::feeds::MAX_REFRESH_INTERVAL_SECS;
+use leptos::*;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Path of the configuration file, unless overridden by RSS_NEWSPAPER_CONFIG
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub database_path: PathBuf,
+    pub renderer: PdfRenderer,
+    // Engine for the pandoc renderer: a LaTeX engine, wkhtmltopdf or weasyprint
+    pub pdf_engine: String,
+    pub typst_path: PathBuf,
+    pub output_dir: PathBuf,
+    // Keep <iframe> embeds when sanitizing feed and article HTML
+    pub allow_iframes: bool,
+    pub http: HttpConfig,
+    pub refresh: RefreshConfig,
+    // Built by `load` once the settings are validated, clones share their connection pools
+    #[serde(skip)]
+    clients: Option<HttpClients>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PdfRenderer {
+    Pandoc,
+    Typst,
+}
+
+impl std::str::FromStr for PdfRenderer {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pandoc" => Ok(PdfRenderer::Pandoc),
+            "typst" => Ok(PdfRenderer::Typst),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub user_agent: String,
+    pub connect_timeout_secs: u64,
+    pub timeout_secs: u64,
+}
+
+#[derive(Clone, Debug)]
+struct HttpClients {
+    client: reqwest::Client,
+    without_redirects: reqwest::Client,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RefreshConfig {
+    // Interval for newly added feeds
+    pub default_interval_secs: i64,
+    // Upper bound for the backoff of failing feeds
+    pub max_backoff_secs: i64,
+    // How often the scheduler looks for feeds that are due
+    pub poll_interval_secs: u64,
+    // Consecutive failures after which a feed is marked dead
+    pub dead_after_failures: i64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            database_path: PathBuf::from("db.sqlite3"),
+            renderer: PdfRenderer::Pandoc,
+            pdf_engine: "xelatex".to_string(),
+            typst_path: PathBuf::from("typst"),
+            output_dir: PathBuf::from("output"),
+            allow_iframes: false,
+            http: HttpConfig::default(),
+            refresh: RefreshConfig::default(),
+            clients: None,
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            user_agent: format!("rss-newspaper-generator/{}", env!("CARGO_PKG_VERSION")),
+            connect_timeout_secs: 10,
+            timeout_secs: 30,
+        }
+    }
+}
+
+impl HttpClients {
+    fn new(http: &HttpConfig) -> Result<HttpClients, reqwest::Error> {
+        let builder = || {
+            reqwest::Client::builder()
+                .user_agent(http.user_agent.clone())
+                .connect_timeout(Duration::from_secs(http.connect_timeout_secs))
+                .timeout(Duration::from_secs(http.timeout_secs))
+        };
+
+        return Ok(HttpClients {
+            client: builder().build()?,
+            without_redirects: builder().redirect(reqwest::redirect::Policy::none()).build()?,
+        });
+    }
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        RefreshConfig {
+            default_interval_secs: 60 * 60,
+            max_backoff_secs: 24 * 60 * 60,
+            poll_interval_secs: 60,
+            dead_after_failures: 10,
+        }
+    }
+}
+
+fn env_override<T: std::str::FromStr>(name: &str, value: &mut T) -> Result<(), String> {
+    if let Ok(raw) = std::env::var(name) {
+        *value = raw
+            .parse()
+            .map_err(|_| format!("Invalid value for {}: {}", name, raw))?;
+    }
+    return Ok(());
+}
+
+impl Config {
+    // Reads the TOML file if it exists, then applies RSS_NEWSPAPER_* environment variables
+    pub fn load() -> Result<Config, String> {
+        let path = std::env::var("RSS_NEWSPAPER_CONFIG").unwrap_or(DEFAULT_CONFIG_PATH.to_string());
+
+        let mut config = match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str::<Config>(&content)
+                .map_err(|e| format!("Error parsing {}: {}", path, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(e) => return Err(format!("Error reading {}: {}", path, e)),
+        };
+
+        env_override("RSS_NEWSPAPER_DATABASE_PATH", &mut config.database_path)?;
+        env_override("RSS_NEWSPAPER_RENDERER", &mut config.renderer)?;
+        env_override("RSS_NEWSPAPER_PDF_ENGINE", &mut config.pdf_engine)?;
+        env_override("RSS_NEWSPAPER_TYPST_PATH", &mut config.typst_path)?;
+        env_override("RSS_NEWSPAPER_OUTPUT_DIR", &mut config.output_dir)?;
+        env_override("RSS_NEWSPAPER_ALLOW_IFRAMES", &mut config.allow_iframes)?;
+        env_override("RSS_NEWSPAPER_USER_AGENT", &mut config.http.user_agent)?;
+        env_override("RSS_NEWSPAPER_HTTP_CONNECT_TIMEOUT", &mut config.http.connect_timeout_secs)?;
+        env_override("RSS_NEWSPAPER_HTTP_TIMEOUT", &mut config.http.timeout_secs)?;
+        env_override("RSS_NEWSPAPER_REFRESH_INTERVAL", &mut config.refresh.default_interval_secs)?;
+        env_override("RSS_NEWSPAPER_MAX_BACKOFF", &mut config.refresh.max_backoff_secs)?;
+        env_override("RSS_NEWSPAPER_POLL_INTERVAL", &mut config.refresh.poll_interval_secs)?;
+        env_override("RSS_NEWSPAPER_DEAD_AFTER_FAILURES", &mut config.refresh.dead_after_failures)?;
+
+        config.validate()?;
+        let clients = HttpClients::new(&config.http).map_err(|e| format!("Error creating HTTP client: {}", e))?;
+        config.clients = Some(clients);
+        return Ok(config);
+    }
+
+    // Values the server would panic or stop working with
+    fn validate(&self) -> Result<(), String> {
+        if self.http.connect_timeout_secs == 0 || self.http.timeout_secs == 0 {
+            return Err("HTTP timeouts must be positive".to_string());
+        }
+        if self.refresh.poll_interval_secs == 0 {
+            return Err("Poll interval must be positive".to_string());
+        }
+        if !(1..=MAX_REFRESH_INTERVAL_SECS).contains(&self.refresh.default_interval_secs) {
+            return Err("Refresh interval must be between 1 second and 30 days".to_string());
+        }
+        if !(1..=MAX_REFRESH_INTERVAL_SECS).contains(&self.refresh.max_backoff_secs) {
+            return Err("Maximum backoff must be between 1 second and 30 days".to_string());
+        }
+        if self.refresh.dead_after_failures <= 0 {
+            return Err("Failures before a feed is marked dead must be positive".to_string());
+        }
+
+        return Ok(());
+    }
+
+    fn clients(&self) -> &HttpClients {
+        return self.clients.as_ref().expect("HTTP clients are built by Config::load");
+    }
+
+    pub fn http_client(&self) -> reqwest::Client {
+        self.clients().client.clone()
+    }
+
+    // Leaves redirects to the caller, which needs to tell permanent ones apart
+    pub fn http_client_without_redirects(&self) -> reqwest::Client {
+        self.clients().without_redirects.clone()
+    }
+}
+
+// The configuration is provided to server functions through the Leptos context
+pub fn use_config<E>() -> Result<Config, ServerFnError<E>> {
+    use_context::<Config>().ok_or_else(|| ServerFnError::ServerError("Configuration missing from context".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_defaults() {
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        let mut config = Config::default();
+        config.refresh.poll_interval_secs = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.http.timeout_secs = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.refresh.max_backoff_secs = i64::MAX;
+        assert!(config.validate().is_err());
+    }
+}
@@ -3,8 +3,10 @@ use leptos::*;
 
 #[component]
 pub fn FormattedDate(date_string: String) -> impl IntoView {
+    // Stored dates are RFC 3339, e.g. 2024-05-23T14:20:31+00:00
     // Example string Thu, 23 May 2024 14:20:31 +0000
-    let datetime = DateTime::parse_from_str(&date_string, "%a, %d %b %Y %H:%M:%S %z")
+    let datetime = DateTime::parse_from_rfc3339(&date_string)
+        .or_else(|_| DateTime::parse_from_str(&date_string, "%a, %d %b %Y %H:%M:%S %z"))
         .unwrap_or_default();
 
     let formatted_date = datetime.format("%a, %d %b %Y").to_string();
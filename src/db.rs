@@ -1,11 +1,22 @@
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use leptos::*;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool};
+use std::time::Duration;
 
 #[cfg(feature = "ssr")]
-pub async fn connect_db() -> SqlitePool {
-    let db_url = "db.sqlite3";
+use crate::config::Config;
+
+// How long a query waits for a lock held by another connection before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Opens the pool and runs migrations, once at startup
+#[cfg(feature = "ssr")]
+pub async fn connect_db(config: &Config) -> SqlitePool {
     let conn_opts = SqliteConnectOptions::new()
-        .filename(db_url)
-        .create_if_missing(true);
+        .filename(&config.database_path)
+        .create_if_missing(true)
+        // WAL lets the UI keep reading while the scheduler writes
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(BUSY_TIMEOUT);
 
     let pool = SqlitePool::connect_with(conn_opts).await.unwrap();
 
@@ -13,3 +24,9 @@ pub async fn connect_db() -> SqlitePool {
     sqlx::migrate!("./migrations").run(&pool).await.unwrap();
     return pool;
 }
+
+// The shared pool is provided to server functions through the Leptos context
+#[cfg(feature = "ssr")]
+pub fn use_pool<E>() -> Result<SqlitePool, ServerFnError<E>> {
+    use_context::<SqlitePool>().ok_or_else(|| ServerFnError::ServerError("Database pool missing from context".to_string()))
+}
@@ -0,0 +1,77 @@
+use crate::config::Config;
+use crate::feeds::{fetch_and_parse_feed, FeedCandidate};
+use scraper::{Html, Selector};
+use url::Url;
+
+const FEED_CONTENT_TYPES: [&str; 4] = [
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/rdf+xml",
+    "application/feed+json",
+];
+
+// Probed when a page doesn't advertise any feeds
+const COMMON_FEED_PATHS: [&str; 7] = [
+    "/feed",
+    "/rss",
+    "/rss.xml",
+    "/atom.xml",
+    "/feed.xml",
+    "/index.xml",
+    "/feed.json",
+];
+
+// Feeds advertised with <link rel="alternate" type="..."> tags
+fn linked_feeds(html: &str, base: &Url) -> Vec<(Url, Option<String>)> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("link[rel~=alternate][href]").unwrap();
+
+    document
+        .select(&selector)
+        .filter(|link| {
+            link.value()
+                .attr("type")
+                .map(|content_type| FEED_CONTENT_TYPES.contains(&content_type.trim().to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .filter_map(|link| {
+            let url = base.join(link.value().attr("href")?).ok()?;
+            let title = link.value().attr("title").map(str::to_string);
+            Some((url, title))
+        })
+        .collect()
+}
+
+// Looks for feeds belonging to an HTML page, keeping only URLs that parse as a feed
+pub async fn discover_feeds(config: &Config, page_url: &str, html: &str) -> Vec<FeedCandidate> {
+    let base = match Url::parse(page_url) {
+        Ok(base) => base,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut urls = linked_feeds(html, &base);
+    if urls.is_empty() {
+        urls = COMMON_FEED_PATHS
+            .iter()
+            .filter_map(|path| base.join(path).ok())
+            .map(|url| (url, None))
+            .collect();
+    }
+
+    let mut candidates: Vec<FeedCandidate> = Vec::new();
+    for (url, title) in urls {
+        let url = url.to_string();
+        if candidates.iter().any(|candidate| candidate.url == url) {
+            continue;
+        }
+
+        if let Ok(channel) = fetch_and_parse_feed(config, url.clone()).await {
+            candidates.push(FeedCandidate {
+                url,
+                title: title.unwrap_or(channel.title),
+            });
+        }
+    }
+
+    return candidates;
+}
@@ -0,0 +1,484 @@
+use crate::layout::Layout;
+use crate::breadcrumbs::{BreadCrumbItem, BreadCrumbs};
+use crate::categories::get_categories;
+use crate::feeds::get_feeds;
+use crate::profiles::{get_profiles, ProfileOptions};
+use leptos::*;
+use leptos_router::*;
+use serde::Deserialize;
+
+#[cfg(feature = "ssr")]
+use crate::article::Article;
+
+#[cfg(feature = "ssr")]
+use crate::feeds::{Feed, FeedItem};
+
+#[cfg(feature = "ssr")]
+use crate::config::Config;
+
+#[cfg(feature = "ssr")]
+use crate::newspaper::PageLayout;
+
+#[cfg(feature = "ssr")]
+use crate::profiles::Profile;
+
+#[cfg(feature = "ssr")]
+use crate::render::{Document, PdfOptions, Rendered};
+
+#[cfg(feature = "ssr")]
+use axum::{extract::{Extension, Query}, response};
+
+#[cfg(feature = "ssr")]
+use sqlx::SqlitePool;
+
+pub const DEFAULT_ITEMS_PER_FEED: usize = 5;
+// Every article of an edition is scraped and rendered in one request
+pub const MAX_ITEMS_PER_FEED: usize = 50;
+
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+pub struct EditionQuery {
+    #[serde(default)]
+    feeds: String,
+    category: Option<i64>,
+    items: Option<usize>,
+    layout: Option<String>,
+    columns: Option<u8>,
+    profile: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+struct EditionArticle {
+    item: FeedItem,
+    content: Article,
+}
+
+#[cfg(feature = "ssr")]
+struct EditionSection {
+    feed: Feed,
+    articles: Vec<EditionArticle>,
+}
+
+#[cfg(feature = "ssr")]
+struct Edition {
+    feed_ids: Vec<i64>,
+    date: chrono::DateTime<chrono::Local>,
+    sections: Vec<EditionSection>,
+}
+
+#[cfg(feature = "ssr")]
+fn parse_feed_ids(feeds: &str) -> Vec<i64> {
+    feeds
+        .split(',')
+        .filter_map(|id| id.trim().parse::<i64>().ok())
+        .collect()
+}
+
+// The explicitly listed feeds followed by those of the category, without duplicates
+#[cfg(feature = "ssr")]
+pub async fn resolve_feed_ids(pool: &SqlitePool, mut feed_ids: Vec<i64>, category: Option<i64>) -> Result<Vec<i64>, String> {
+    use crate::categories::category_feed_ids;
+
+    if let Some(category) = category {
+        let category_ids = category_feed_ids(pool, category)
+            .await
+            .map_err(|e| format!("Error loading category {}: {}", category, e))?;
+        for id in category_ids {
+            if !feed_ids.contains(&id) {
+                feed_ids.push(id);
+            }
+        }
+    }
+
+    return Ok(feed_ids);
+}
+
+// The newest `count` stored items of the feed that link to an article. Feeds are kept
+// up to date by the scheduler, so editions don't fetch them again.
+#[cfg(feature = "ssr")]
+async fn build_section(pool: &SqlitePool, config: &Config, feed: Feed, count: usize) -> Result<EditionSection, String> {
+    use crate::article::get_article;
+
+    let items = sqlx::query_as::<_, FeedItem>(
+        "SELECT * FROM items WHERE feed_id = ? AND link IS NOT NULL
+        ORDER BY pub_date DESC, first_seen DESC
+        LIMIT ?",
+    )
+    .bind(feed.id)
+    .bind(count as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|err| format!("Error loading items of {}: {}", feed.url, err))?;
+
+    let mut articles = Vec::new();
+    for item in items {
+        let link = item.link.clone().unwrap_or_default();
+        match get_article(pool, config, link, false).await {
+            Ok(content) => articles.push(EditionArticle { item, content }),
+            // A single broken article shouldn't spoil the whole edition
+            Err(err) => logging::warn!("{}", err),
+        }
+    }
+
+    return Ok(EditionSection { feed, articles });
+}
+
+#[cfg(feature = "ssr")]
+async fn build_edition(pool: &SqlitePool, config: &Config, feed_ids: &[i64], count: usize) -> Result<Edition, String> {
+    if feed_ids.is_empty() {
+        return Err("No feeds selected for edition".to_string());
+    }
+    let count = count.clamp(1, MAX_ITEMS_PER_FEED);
+
+    let mut sections = Vec::new();
+    for &id in feed_ids {
+        let feed = match sqlx::query_as::<_, Feed>("SELECT * FROM feeds WHERE id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+        {
+            Ok(feed) => feed,
+            Err(err) => {
+                logging::warn!("Leaving feed {} out of the edition: {}", id, err);
+                continue;
+            }
+        };
+
+        // Like a broken article, a broken feed shouldn't spoil the whole edition
+        match build_section(pool, config, feed, count).await {
+            Ok(section) => sections.push(section),
+            Err(err) => logging::warn!("{}", err),
+        }
+    }
+
+    return Ok(Edition { feed_ids: feed_ids.to_vec(), date: chrono::Local::now(), sections });
+}
+
+// The edition is recorded before it's rendered, its id is the number in the masthead.
+// Editions built at the same time get numbers of their own.
+#[cfg(feature = "ssr")]
+async fn record_edition(pool: &SqlitePool, edition: &Edition) -> Result<i64, String> {
+    let feeds = edition.feed_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",");
+    return sqlx::query_scalar::<_, i64>("INSERT INTO editions (built_at, feed_ids) VALUES (?, ?) RETURNING id")
+        .bind(edition.date.to_rfc3339())
+        .bind(feeds)
+        .fetch_one(pool)
+        .await
+        .map_err(|err| format!("Error recording edition: {}", err));
+}
+
+// Editions that couldn't be rendered aren't kept
+#[cfg(feature = "ssr")]
+async fn discard_edition(pool: &SqlitePool, number: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM editions WHERE id = ?")
+        .bind(number)
+        .execute(pool)
+        .await?;
+
+    return Ok(());
+}
+
+#[cfg(feature = "ssr")]
+fn byline(article: &EditionArticle, feed: &Feed) -> String {
+    let mut parts = Vec::new();
+    if let Some(author) = &article.item.author {
+        parts.push(author.clone());
+    }
+    parts.push(feed.display_title());
+    if let Some(pub_date) = article.item.pub_date.as_deref().and_then(|date| chrono::DateTime::parse_from_rfc3339(date).ok()) {
+        parts.push(pub_date.format("%d %B %Y").to_string());
+    }
+
+    return parts.join(" · ");
+}
+
+#[cfg(feature = "ssr")]
+async fn format_edition(config: &Config, edition: &Edition, profile: Option<&Profile>, layout: PageLayout) -> String {
+    use crate::article::embedded_content;
+    use crate::newspaper;
+    use crate::sanitize::escape_text;
+
+    let mut html = String::new();
+
+    for section in &edition.sections {
+        html.push_str(&format!("<h1>{}</h1>", escape_text(&section.feed.display_title())));
+        for article in &section.articles {
+            let content = embedded_content(config, &article.content, profile, layout).await;
+            if let PageLayout::Newspaper { .. } = layout {
+                html.push_str(&newspaper::format_article(
+                    2,
+                    &article.content.title,
+                    &byline(article, &section.feed),
+                    &article.content.description,
+                    &article.content.text,
+                    &content,
+                ));
+                continue;
+            }
+            html.push_str(&format!(
+                "<h2>{}</h2><p class=\"byline\">{}</p><p class=\"italic\">{}</p>{}",
+                escape_text(&article.content.title),
+                escape_text(&byline(article, &section.feed)),
+                escape_text(&article.content.description),
+                content
+            ));
+        }
+    }
+
+    return html;
+}
+
+#[cfg(feature = "ssr")]
+async fn edition_document(
+    config: &Config,
+    edition: &Edition,
+    number: Option<i64>,
+    date: String,
+    profile: Option<Profile>,
+    layout: PageLayout,
+) -> Document {
+    let feed_titles = edition
+        .sections
+        .iter()
+        .map(|section| section.feed.display_title())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    return Document {
+        title: Some("RSS Newspaper".to_string()),
+        subtitle: Some(feed_titles),
+        date: Some(date),
+        html: format_edition(config, edition, profile.as_ref(), layout).await,
+        table_of_contents: true,
+        layout,
+        number,
+        issued: Some(edition.date),
+        profile,
+        ..Document::default()
+    };
+}
+
+#[cfg(feature = "ssr")]
+pub async fn edition_pdf(
+    pool: &SqlitePool,
+    config: &Config,
+    feed_ids: &[i64],
+    count: usize,
+    options: PdfOptions,
+) -> Result<Rendered, String> {
+    use crate::render::{pdf_renderer, render_blocking};
+
+    let edition = build_edition(pool, config, feed_ids, count).await?;
+    let number = record_edition(pool, &edition).await?;
+    let date = edition.date.format("%A, %d %B %Y").to_string();
+
+    let document = edition_document(config, &edition, Some(number), date, options.profile, options.layout).await;
+    let pdf = render_blocking(pdf_renderer(config), document).await;
+    if pdf.is_err() {
+        if let Err(err) = discard_edition(pool, number).await {
+            logging::warn!("Error discarding edition {}: {}", number, err);
+        }
+    }
+
+    return pdf;
+}
+
+#[cfg(feature = "ssr")]
+pub async fn edition_epub(pool: &SqlitePool, config: &Config, feed_ids: &[i64], count: usize) -> Result<Rendered, String> {
+    use crate::render::{epub_renderer, render_blocking};
+
+    let edition = build_edition(pool, config, feed_ids, count).await?;
+    let date = edition.date.format("%Y-%m-%d").to_string();
+
+    let document = edition_document(config, &edition, None, date, None, PageLayout::Standard).await;
+    return render_blocking(epub_renderer(), document).await;
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_edition_pdf(
+    Extension(config): Extension<Config>,
+    Extension(pool): Extension<SqlitePool>,
+    query: Query<EditionQuery>,
+) -> response::Response {
+    use crate::article::{error_response, file_response};
+
+    let feed_ids = match resolve_feed_ids(&pool, parse_feed_ids(&query.feeds), query.category).await {
+        Ok(feed_ids) => feed_ids,
+        Err(e) => return error_response(e),
+    };
+    let count = query.items.unwrap_or(DEFAULT_ITEMS_PER_FEED);
+    let options = match PdfOptions::load(&pool, query.layout.as_deref(), query.columns, query.profile.as_deref()).await {
+        Ok(options) => options,
+        Err(e) => return error_response(e),
+    };
+
+    match edition_pdf(&pool, &config, &feed_ids, count, options).await {
+        Ok(pdf) => file_response(pdf, "edition.pdf"),
+        Err(e) => error_response(e),
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_edition_epub(
+    Extension(config): Extension<Config>,
+    Extension(pool): Extension<SqlitePool>,
+    query: Query<EditionQuery>,
+) -> response::Response {
+    use crate::article::{error_response, file_response};
+
+    let feed_ids = match resolve_feed_ids(&pool, parse_feed_ids(&query.feeds), query.category).await {
+        Ok(feed_ids) => feed_ids,
+        Err(e) => return error_response(e),
+    };
+    let count = query.items.unwrap_or(DEFAULT_ITEMS_PER_FEED);
+
+    match edition_epub(&pool, &config, &feed_ids, count).await {
+        Ok(epub) => file_response(epub, "edition.epub"),
+        Err(e) => error_response(e),
+    }
+}
+
+#[component]
+pub fn EditionView() -> impl IntoView {
+    let feeds = create_resource(|| (), |_| async move { get_feeds().await.unwrap_or_default() });
+    let categories = create_resource(|| (), |_| async move { get_categories().await.unwrap_or_default() });
+    let profiles = create_resource(|| (), |_| async move { get_profiles().await.unwrap_or_default() });
+
+    // The feed list links here with a category preselected
+    let query = use_query_map();
+    let initial_category = query.with_untracked(|query| query.get("category").and_then(|id| id.parse::<i64>().ok()));
+
+    let (selected, set_selected) = create_signal(Vec::<i64>::new());
+    let (category, set_category) = create_signal(initial_category);
+    let (items, set_items) = create_signal(DEFAULT_ITEMS_PER_FEED);
+    // Number of newspaper columns, the standard layout when None
+    let (columns, set_columns) = create_signal(None::<u8>);
+    let (profile, set_profile) = create_signal(String::new());
+
+    let toggle_feed = move |id: i64, checked: bool| {
+        set_selected.update(|selected| {
+            selected.retain(|selected_id| *selected_id != id);
+            if checked {
+                selected.push(id);
+            }
+        });
+    };
+
+    let edition_url = move |format: &str| {
+        let feeds = selected
+            .get()
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let category = category
+            .get()
+            .map(|category| format!("&category={}", category))
+            .unwrap_or_default();
+        // EPUBs reflow, the layout and profile only apply to PDFs
+        let mut layout = match (format, columns.get()) {
+            ("pdf", Some(columns)) => format!("&layout=newspaper&columns={}", columns),
+            _ => String::new(),
+        };
+        if format == "pdf" && !profile.get().is_empty() {
+            layout.push_str(&format!("&profile={}", profile.get()));
+        }
+        format!("/edition/{}?feeds={}{}&items={}{}", format, feeds, category, items.get(), layout)
+    };
+
+    view! {
+        <Layout headline="Edition".to_string()>
+            <BreadCrumbs items=vec![
+                BreadCrumbItem { text: "Feeds".to_string(), url: "/feeds".to_string() },
+                BreadCrumbItem { text: "Edition".to_string(), url: "/edition".to_string() },
+            ] />
+            <div class="max-w-[700px]">
+                <Suspense fallback=|| view! { <p>Loading...</p> }>
+                    {move || feeds.get().map(|feeds| view! {
+                        <ul>
+                            <For
+                                each=move || feeds.clone()
+                                key=|feed| feed.id
+                                children=move |feed| {
+                                    let id = feed.id;
+                                    view! {
+                                        <li class="my-2">
+                                            <label class="flex items-center gap-2">
+                                                <input
+                                                    type="checkbox"
+                                                    on:change=move |ev| toggle_feed(id, event_target_checked(&ev))
+                                                />
+                                                {feed.display_title()}
+                                            </label>
+                                        </li>
+                                    }
+                                }
+                            />
+                        </ul>
+                    })}
+                </Suspense>
+                <Suspense fallback=|| ()>
+                    {move || categories.get().map(|categories| view! {
+                        <label class="flex items-center gap-2 my-4">
+                            "All feeds of category"
+                            <select
+                                class="p-2 rounded border"
+                                on:change=move |ev| set_category(event_target_value(&ev).parse::<i64>().ok())
+                            >
+                                <option value="">None</option>
+                                {categories
+                                    .into_iter()
+                                    .map(|c| view! {
+                                        <option value=c.id.to_string() selected=initial_category == Some(c.id)>{c.name}</option>
+                                    })
+                                    .collect_view()}
+                            </select>
+                        </label>
+                    })}
+                </Suspense>
+                <label class="flex items-center gap-2 my-4">
+                    "Articles per feed"
+                    <input
+                        class="p-2 rounded border w-20"
+                        type="number"
+                        min="1"
+                        max=MAX_ITEMS_PER_FEED
+                        prop:value=move || items.get()
+                        on:input=move |ev| set_items(event_target_value(&ev).parse().unwrap_or(DEFAULT_ITEMS_PER_FEED))
+                    />
+                </label>
+                <label class="flex items-center gap-2 my-4">
+                    "PDF layout"
+                    <select
+                        class="p-2 rounded border"
+                        on:change=move |ev| set_columns(event_target_value(&ev).parse::<u8>().ok())
+                    >
+                        <option value="">Standard</option>
+                        <option value="2">Newspaper, two columns</option>
+                        <option value="3">Newspaper, three columns</option>
+                    </select>
+                </label>
+                <Suspense fallback=|| ()>
+                    {move || profiles.get().map(|profiles| view! {
+                        <label class="flex items-center gap-2 my-4">
+                            "PDF profile"
+                            <select class="p-2 rounded border" on:change=move |ev| set_profile(event_target_value(&ev))>
+                                <ProfileOptions profiles />
+                            </select>
+                            <a class="text-sm" href="/profiles">Manage profiles</a>
+                        </label>
+                    })}
+                </Suspense>
+                <Show
+                    when=move || !selected.get().is_empty() || category.get().is_some()
+                    fallback=|| view! { <p>Select at least one feed or a category.</p> }
+                >
+                    <div class="flex gap-2">
+                        <a class="p-2 rounded bg-slate-100" download href=move || edition_url("pdf")>Download Edition as PDF</a>
+                        <a class="p-2 rounded bg-slate-100" download href=move || edition_url("epub")>Download Edition as EPUB</a>
+                    </div>
+                </Show>
+            </div>
+        </Layout>
+    }
+}
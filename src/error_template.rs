@@ -1,3 +1,4 @@
+use crate::errors::FeedError;
 use http::status::StatusCode;
 use leptos::*;
 use thiserror::Error;
@@ -6,12 +7,27 @@ use thiserror::Error;
 pub enum AppError {
     #[error("Not Found")]
     NotFound,
+    #[error("{0}")]
+    Feed(FeedError),
+    #[error("{0}")]
+    Internal(String),
 }
 
 impl AppError {
     pub fn status_code(&self) -> StatusCode {
         match self {
             AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Feed(err) => err.status_code(),
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<ServerFnError<FeedError>> for AppError {
+    fn from(err: ServerFnError<FeedError>) -> Self {
+        match err {
+            ServerFnError::WrappedServerError(err) => AppError::Feed(err),
+            err => AppError::Internal(err.to_string()),
         }
     }
 }
@@ -33,10 +49,19 @@ pub fn ErrorTemplate(
     // Get Errors from Signal
     let errors = errors.get_untracked();
 
-    // Downcast lets us take a type that implements `std::error::Error`
+    // Downcast lets us take a type that implements `std::error::Error`,
+    // errors of the feed server functions keep their status code
     let errors: Vec<AppError> = errors
         .into_iter()
-        .filter_map(|(_k, v)| v.downcast_ref::<AppError>().cloned())
+        .map(|(_k, v)| {
+            if let Some(err) = v.downcast_ref::<AppError>() {
+                return err.clone();
+            }
+            if let Some(err) = v.downcast_ref::<ServerFnError<FeedError>>() {
+                return AppError::from(err.clone());
+            }
+            return AppError::Internal(v.to_string());
+        })
         .collect();
     println!("Errors: {errors:#?}");
 
@@ -46,8 +71,8 @@ pub fn ErrorTemplate(
     {
         use leptos_axum::ResponseOptions;
         let response = use_context::<ResponseOptions>();
-        if let Some(response) = response {
-            response.set_status(errors[0].status_code());
+        if let (Some(response), Some(error)) = (response, errors.first()) {
+            response.set_status(error.status_code());
         }
     }
 
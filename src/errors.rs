@@ -0,0 +1,154 @@
+use http::status::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// Errors of the feed and article server functions. Server functions send custom errors
+// as their Display string and parse them back with FromStr, so every variant starts
+// with a prefix of its own.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeedError {
+    InvalidUrl(String),
+    InvalidInput(String),
+    NotAFeed(String),
+    Timeout(String),
+    HttpStatus(u16),
+    Network(String),
+    Parse(String),
+    Extraction(String),
+    DuplicateFeed(String),
+    NotFound(String),
+    Database(String),
+}
+
+const INVALID_URL: &str = "Invalid URL: ";
+const INVALID_INPUT: &str = "Invalid input: ";
+const NOT_A_FEED: &str = "No feed found at ";
+const TIMEOUT: &str = "Timed out: ";
+const HTTP_STATUS: &str = "Server responded with HTTP ";
+const NETWORK: &str = "Network error: ";
+const PARSE: &str = "Could not parse feed: ";
+const EXTRACTION: &str = "Could not extract article: ";
+const DUPLICATE_FEED: &str = "Already subscribed to ";
+const NOT_FOUND: &str = "Not found: ";
+const DATABASE: &str = "Database error: ";
+
+impl fmt::Display for FeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedError::InvalidUrl(url) => write!(f, "{}{}", INVALID_URL, url),
+            FeedError::InvalidInput(message) => write!(f, "{}{}", INVALID_INPUT, message),
+            FeedError::NotAFeed(url) => write!(f, "{}{}", NOT_A_FEED, url),
+            FeedError::Timeout(url) => write!(f, "{}{}", TIMEOUT, url),
+            FeedError::HttpStatus(status) => write!(f, "{}{}", HTTP_STATUS, status),
+            FeedError::Network(message) => write!(f, "{}{}", NETWORK, message),
+            FeedError::Parse(message) => write!(f, "{}{}", PARSE, message),
+            FeedError::Extraction(message) => write!(f, "{}{}", EXTRACTION, message),
+            FeedError::DuplicateFeed(url) => write!(f, "{}{}", DUPLICATE_FEED, url),
+            FeedError::NotFound(what) => write!(f, "{}{}", NOT_FOUND, what),
+            FeedError::Database(message) => write!(f, "{}{}", DATABASE, message),
+        }
+    }
+}
+
+impl std::error::Error for FeedError {}
+
+impl FromStr for FeedError {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let variants: [(&str, fn(String) -> FeedError); 10] = [
+            (INVALID_URL, FeedError::InvalidUrl),
+            (INVALID_INPUT, FeedError::InvalidInput),
+            (NOT_A_FEED, FeedError::NotAFeed),
+            (TIMEOUT, FeedError::Timeout),
+            (NETWORK, FeedError::Network),
+            (PARSE, FeedError::Parse),
+            (EXTRACTION, FeedError::Extraction),
+            (DUPLICATE_FEED, FeedError::DuplicateFeed),
+            (NOT_FOUND, FeedError::NotFound),
+            (DATABASE, FeedError::Database),
+        ];
+
+        if let Some(status) = s.strip_prefix(HTTP_STATUS) {
+            return status.parse().map(FeedError::HttpStatus).map_err(|_| ());
+        }
+        for (prefix, variant) in variants {
+            if let Some(rest) = s.strip_prefix(prefix) {
+                return Ok(variant(rest.to_string()));
+            }
+        }
+
+        return Err(());
+    }
+}
+
+impl FeedError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            FeedError::InvalidUrl(_) | FeedError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            FeedError::NotAFeed(_) | FeedError::Extraction(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            FeedError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            FeedError::HttpStatus(_) | FeedError::Network(_) | FeedError::Parse(_) => StatusCode::BAD_GATEWAY,
+            FeedError::DuplicateFeed(_) => StatusCode::CONFLICT,
+            FeedError::NotFound(_) => StatusCode::NOT_FOUND,
+            FeedError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<sqlx::Error> for FeedError {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => FeedError::NotFound("No matching row".to_string()),
+            err => FeedError::Database(err.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<reqwest::Error> for FeedError {
+    fn from(err: reqwest::Error) -> Self {
+        let url = err.url().map(|url| url.to_string()).unwrap_or_default();
+        if err.is_timeout() {
+            return FeedError::Timeout(url);
+        }
+        match err.status() {
+            Some(status) => FeedError::HttpStatus(status.as_u16()),
+            None => FeedError::Network(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_display() {
+        let errors = [
+            FeedError::InvalidUrl("not a url".to_string()),
+            FeedError::InvalidInput("Refresh interval must be positive".to_string()),
+            FeedError::NotAFeed("https://example.com/".to_string()),
+            FeedError::Timeout("https://example.com/feed".to_string()),
+            FeedError::HttpStatus(404),
+            FeedError::Network("connection refused".to_string()),
+            FeedError::Parse("unexpected end of input".to_string()),
+            FeedError::Extraction("no article found".to_string()),
+            FeedError::DuplicateFeed("https://example.com/feed".to_string()),
+            FeedError::NotFound("No matching row".to_string()),
+            FeedError::Database("database is locked".to_string()),
+        ];
+
+        for error in errors {
+            assert_eq!(error.to_string().parse::<FeedError>(), Ok(error));
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_messages() {
+        assert_eq!("Something else went wrong".parse::<FeedError>(), Err(()));
+        assert_eq!("Server responded with HTTP teapot".parse::<FeedError>(), Err(()));
+    }
+}
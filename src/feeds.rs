@@ -1,14 +1,27 @@
 use crate::layout::Layout;
+use crate::article::article_href;
 use crate::breadcrumbs::{BreadCrumbItem, BreadCrumbs};
 use crate::date::FormattedDate;
+use crate::error_template::ErrorTemplate;
+use crate::errors::FeedError;
+use crate::categories::{
+    get_categories, get_feed_categories, AddCategory, Category, DeleteCategory, FeedCategorySettings,
+    RefreshCategory,
+};
 
 use leptos::*;
 use leptos_router::*;
-use rss::{Channel, Item};
 use serde::{Deserialize, Serialize};
-use std::error::Error;
 use url::Url;
 
+#[cfg(feature = "ssr")]
+use crate::config::Config;
+#[cfg(feature = "ssr")]
+use crate::syndication::Channel;
+
+// Longest refresh interval of a feed, 30 days
+pub const MAX_REFRESH_INTERVAL_SECS: i64 = 30 * 24 * 60 * 60;
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
 pub struct Feed {
@@ -16,15 +29,56 @@ pub struct Feed {
     pub url: String,
     pub title: String,
     pub description: String,
+    pub refresh_interval: i64,
+    pub last_fetched: Option<String>,
+    pub last_success: Option<String>,
+    pub last_error: Option<String>,
+    pub failure_count: i64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub custom_title: Option<String>,
+    pub notes: String,
+    pub redirect_url: Option<String>,
+    pub redirect_count: i64,
+    pub dead: bool,
 }
 
-#[derive(Clone, PartialEq, Serialize, Deserialize)]
-pub struct FeedDetails {
-    pub feed: Feed,
-    pub channel: Channel,
+impl Feed {
+    pub fn display_title(&self) -> String {
+        return self.custom_title.clone().unwrap_or_else(|| self.title.clone());
+    }
 }
 
-fn is_valid_url(url: String) -> bool {
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct FeedItem {
+    pub id: i64,
+    pub feed_id: i64,
+    pub guid: String,
+    pub link: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub pub_date: Option<String>,
+    pub first_seen: String,
+    pub author: Option<String>,
+    pub read: bool,
+    pub starred: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedCandidate {
+    pub url: String,
+    pub title: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AddFeedResult {
+    Added,
+    // The URL wasn't a feed, but these feeds were found on the page
+    Candidates(Vec<FeedCandidate>),
+}
+
+pub(crate) fn is_valid_url(url: String) -> bool {
     let parsed_url = match Url::parse(&url) {
         Ok(url) => url,
         Err(_) => return false,
@@ -36,165 +90,649 @@ fn is_valid_url(url: String) -> bool {
     }
 }
 
-async fn is_valid_rss_feed(url: String) -> bool {
-    if !is_valid_url(url.clone()) {
-        return false;
+#[server]
+pub async fn get_feed(id: i64) -> Result<Feed, ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+
+    let feed = sqlx::query_as::<_, Feed>("SELECT * FROM feeds WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(FeedError::from)?
+        .ok_or_else(|| FeedError::NotFound(format!("feed {}", id)))?;
+
+    return Ok(feed);
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn fetch_text(config: &Config, url: String) -> Result<String, FeedError> {
+    let response = config.http_client().get(url).send().await?.error_for_status()?;
+    return Ok(response.text().await?);
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn fetch_and_parse_feed(config: &Config, url: String) -> Result<Channel, FeedError> {
+    use crate::syndication;
+
+    let content = fetch_text(config, url).await?;
+    let channel = syndication::parse(&content).map_err(FeedError::Parse)?;
+    return Ok(channel);
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) enum FetchResult {
+    NotModified,
+    Fetched {
+        channel: Channel,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+    Gone,
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) struct Fetch {
+    pub result: FetchResult,
+    // Set when every redirect on the way was permanent
+    pub moved_to: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+const MAX_REDIRECTS: usize = 10;
+
+// Fetches a permanent redirect needs to be seen on before the feed's URL follows it
+#[cfg(feature = "ssr")]
+const REDIRECT_CONFIRMATIONS: i64 = 3;
+
+// Sends the validators from the previous fetch, so unchanged feeds answer with 304.
+// Redirects are followed by hand to find out whether the feed has moved for good.
+#[cfg(feature = "ssr")]
+pub(crate) async fn fetch_feed_if_modified(config: &Config, feed: &Feed) -> Result<Fetch, FeedError> {
+    use crate::syndication;
+    use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LOCATION};
+    use reqwest::StatusCode;
+
+    let client = config.http_client_without_redirects();
+    let mut url = feed.url.clone();
+    let mut moved_to = None;
+    let mut permanent = true;
+    let mut redirects = 0;
+
+    let response = loop {
+        let mut request = client.get(&url);
+        if let Some(etag) = &feed.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &feed.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_redirection() || status == StatusCode::NOT_MODIFIED {
+            break response;
+        }
+
+        redirects += 1;
+        if redirects > MAX_REDIRECTS {
+            return Err(FeedError::Network(format!("Too many redirects from {}", feed.url)));
+        }
+
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| FeedError::Network(format!("Redirect ({}) without a location", status)))?;
+        let next = Url::parse(&url)
+            .and_then(|base| base.join(location))
+            .map_err(|_| FeedError::InvalidUrl(location.to_string()))?
+            .to_string();
+
+        permanent = permanent && matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT);
+        moved_to = if permanent { Some(next.clone()) } else { None };
+        url = next;
+    };
+
+    match response.status() {
+        StatusCode::NOT_MODIFIED => return Ok(Fetch { result: FetchResult::NotModified, moved_to }),
+        StatusCode::GONE => return Ok(Fetch { result: FetchResult::Gone, moved_to }),
+        _ => {}
     }
+    let response = response.error_for_status()?;
 
-    let response = match reqwest::get(url).await {
-        Ok(response) => response,
-        Err(_) => return false,
+    let header = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
     };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+
+    let content = response.text().await?;
+    let channel = syndication::parse(&content).map_err(FeedError::Parse)?;
+
+    return Ok(Fetch {
+        result: FetchResult::Fetched { channel, etag, last_modified },
+        moved_to,
+    });
+}
+
+// Switches the feed to the redirect target once it has been confirmed, unless another
+// subscription has that URL already. Any other response resets the count.
+#[cfg(feature = "ssr")]
+async fn record_redirect(pool: &sqlx::SqlitePool, feed: &Feed, moved_to: Option<String>) -> Result<(), sqlx::Error> {
+    let target = match moved_to {
+        Some(target) => target,
+        None if feed.redirect_url.is_some() => {
+            sqlx::query("UPDATE feeds SET redirect_url = NULL, redirect_count = 0 WHERE id = ?")
+                .bind(feed.id)
+                .execute(pool)
+                .await?;
+            return Ok(());
+        }
+        None => return Ok(()),
+    };
+
+    let count = match &feed.redirect_url {
+        Some(redirect_url) if *redirect_url == target => feed.redirect_count + 1,
+        _ => 1,
+    };
+
+    let confirmed = count >= REDIRECT_CONFIRMATIONS;
+    if confirmed && !is_subscribed(pool, &target, Some(feed.id)).await? {
+        logging::log!("Feed {} moved to {}", feed.url, target);
+        sqlx::query("UPDATE feeds SET url = ?, redirect_url = NULL, redirect_count = 0 WHERE id = ?")
+            .bind(target)
+            .bind(feed.id)
+            .execute(pool)
+            .await?;
+    } else {
+        // The redirect stays on record, so the feed shows where it moved to
+        if confirmed {
+            logging::warn!("Feed {} moved to {}, which is subscribed already", feed.url, target);
+        }
+        sqlx::query("UPDATE feeds SET redirect_url = ?, redirect_count = ? WHERE id = ?")
+            .bind(target)
+            .bind(count)
+            .bind(feed.id)
+            .execute(pool)
+            .await?;
+    }
+
+    return Ok(());
+}
+
+// Items without a guid fall back to their link, then their title
+#[cfg(feature = "ssr")]
+fn item_guid(item: &crate::syndication::ChannelItem) -> Option<String> {
+    item.guid
+        .clone()
+        .or_else(|| item.link.clone())
+        .or_else(|| item.title.clone())
+}
 
-    if let Some(content_type) = response.headers().get(reqwest::header::CONTENT_TYPE) {
-        let content_type = match content_type.to_str() {
-            Ok(content_type) => content_type.to_lowercase(),
-            Err(_) => return false,
+#[cfg(feature = "ssr")]
+pub async fn store_items(
+    pool: &sqlx::SqlitePool,
+    feed_id: i64,
+    channel: &Channel,
+) -> Result<(), sqlx::Error> {
+    use crate::sanitize::{html_to_text, safe_link};
+
+    for item in &channel.items {
+        let guid = match item_guid(item) {
+            Some(guid) => guid,
+            None => continue,
         };
 
-        if content_type.contains("application/rss+xml")
-            || content_type.contains("application/xml")
-            || content_type.contains("text/xml")
-        {
-            return true;
+        let id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO items (feed_id, guid, link, title, description, pub_date, author)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (feed_id, guid) DO UPDATE SET
+                link = excluded.link,
+                title = excluded.title,
+                description = excluded.description,
+                pub_date = excluded.pub_date,
+                author = excluded.author
+            RETURNING id",
+        )
+        .bind(feed_id)
+        .bind(guid)
+        .bind(item.link.as_deref().and_then(safe_link))
+        .bind(item.title.clone())
+        .bind(item.description.clone())
+        // Dates are stored as UTC RFC 3339 so they sort correctly in SQL
+        .bind(item.pub_date.map(|date| date.to_rfc3339()))
+        .bind(item.author.clone())
+        .fetch_one(pool)
+        .await?;
+
+        sqlx::query("DELETE FROM items_fts WHERE rowid = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+        sqlx::query("INSERT INTO items_fts (rowid, title, description) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(item.title.clone().unwrap_or_default())
+            .bind(html_to_text(&item.description.clone().unwrap_or_default()))
+            .execute(pool)
+            .await?;
+    }
+
+    return Ok(());
+}
+
+// Fetches the feed, stores its items and records the outcome on the feed
+#[cfg(feature = "ssr")]
+pub async fn refresh_feed(pool: &sqlx::SqlitePool, config: &Config, feed: &Feed) -> Result<(), FeedError> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let fetch = fetch_feed_if_modified(config, feed).await;
+    if let Ok(fetch) = &fetch {
+        if let Err(err) = record_redirect(pool, feed, fetch.moved_to.clone()).await {
+            logging::warn!("Error recording redirect for {}: {}", feed.url, err);
         }
-        return false;
     }
 
-    return false;
+    let mut gone = false;
+    let result = match fetch {
+        // Nothing changed since the last fetch, which still counts as a success
+        Ok(Fetch { result: FetchResult::NotModified, .. }) => Ok(()),
+        Ok(Fetch { result: FetchResult::Fetched { channel, etag, last_modified }, .. }) => {
+            store_channel(pool, feed.id, &channel, etag, last_modified)
+                .await
+                .map_err(FeedError::from)
+        }
+        Ok(Fetch { result: FetchResult::Gone, .. }) => {
+            gone = true;
+            Err(FeedError::HttpStatus(410))
+        }
+        Err(err) => Err(err),
+    };
+
+    // Without these the backoff would stop, so failing to record the outcome is an error too
+    let recorded = match &result {
+        Ok(()) => sqlx::query(
+            "UPDATE feeds SET last_fetched = ?, last_success = ?, last_error = NULL, failure_count = 0, dead = 0
+            WHERE id = ?",
+        )
+        .bind(&now)
+        .bind(&now)
+        .bind(feed.id)
+        .execute(pool)
+        .await,
+        Err(err) => sqlx::query(
+            "UPDATE feeds SET last_fetched = ?, last_error = ?, failure_count = failure_count + 1,
+                dead = CASE WHEN ? OR failure_count + 1 >= ? THEN 1 ELSE dead END
+            WHERE id = ?",
+        )
+        .bind(&now)
+        .bind(err.to_string())
+        .bind(gone)
+        .bind(config.refresh.dead_after_failures)
+        .bind(feed.id)
+        .execute(pool)
+        .await,
+    };
+    recorded?;
+
+    return result;
+}
+
+#[cfg(feature = "ssr")]
+async fn store_channel(
+    pool: &sqlx::SqlitePool,
+    feed_id: i64,
+    channel: &Channel,
+    etag: Option<String>,
+    last_modified: Option<String>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE feeds SET title = ?, description = ?, etag = ?, last_modified = ? WHERE id = ?")
+        .bind(channel.title.clone())
+        .bind(channel.description.clone())
+        .bind(etag)
+        .bind(last_modified)
+        .bind(feed_id)
+        .execute(pool)
+        .await?;
+
+    return store_items(pool, feed_id, channel).await;
+}
+
+// Descriptions are rendered with inner_html and links end up in href, so neither leaves
+// the server unsanitized. Items stored before links were checked are cleaned here too.
+#[cfg(feature = "ssr")]
+pub(crate) fn sanitize_item(mut item: FeedItem, allow_iframes: bool) -> FeedItem {
+    use crate::sanitize::{safe_link, sanitize_html};
+
+    item.description = item
+        .description
+        .map(|description| sanitize_html(&description, allow_iframes));
+    item.link = item.link.and_then(|link| safe_link(&link));
+    return item;
 }
 
 #[server]
-pub async fn get_feed(id: i64) -> Result<Feed, ServerFnError> {
-    use crate::db::connect_db;
+pub async fn get_feed_items(feed_id: i64) -> Result<Vec<FeedItem>, ServerFnError<FeedError>> {
+    use crate::config::use_config;
+    use crate::db::use_pool;
 
-    let pool = connect_db().await;
+    let config = use_config()?;
+    let pool = use_pool()?;
 
-    let feed = sqlx::query_as::<_, Feed>("SELECT * FROM feeds WHERE id = ?")
-        .bind(id)
-        .fetch_one(&pool)
-        .await?;
+    let items = sqlx::query_as::<_, FeedItem>(
+        "SELECT * FROM items WHERE feed_id = ? ORDER BY pub_date DESC, first_seen DESC",
+    )
+    .bind(feed_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(FeedError::from)?;
 
-    return Ok(feed);
+    return Ok(items
+        .into_iter()
+        .map(|item| sanitize_item(item, config.allow_iframes))
+        .collect());
 }
 
-async fn fetch_and_parse_rss(url: String) -> Result<Channel, Box<dyn Error>> {
-    let content = reqwest::get(url).await?.text().await?;
-    let channel = content.parse::<Channel>()?;
-    return Ok(channel);
+// Number of unread items per feed id, feeds without unread items are left out
+#[server]
+pub async fn get_unread_counts() -> Result<Vec<(i64, i64)>, ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    let counts = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT feed_id, COUNT(*) FROM items WHERE read = 0 GROUP BY feed_id",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(FeedError::from)?;
+
+    return Ok(counts);
 }
 
 #[server]
-async fn get_channel(id: i64) -> Result<Channel, ServerFnError> {
-    let feed = match get_feed(id).await {
-        Ok(feed) => feed,
-        Err(err) => return Err(ServerFnError::new(format!("Error fetching feed: {}", err))),
-    };
+pub async fn get_starred_items() -> Result<Vec<FeedItem>, ServerFnError<FeedError>> {
+    use crate::config::use_config;
+    use crate::db::use_pool;
 
-    let channel = match fetch_and_parse_rss(feed.url).await {
-        Ok(channel) => channel,
-        Err(err) => {
-            return Err(ServerFnError::new(format!(
-                "Error fetching RSS feed: {}",
-                err
-            )))
-        }
-    };
+    let config = use_config()?;
+    let pool = use_pool()?;
 
-    return Ok(channel);
+    let items = sqlx::query_as::<_, FeedItem>(
+        "SELECT * FROM items WHERE starred = 1 ORDER BY pub_date DESC, first_seen DESC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(FeedError::from)?;
+
+    return Ok(items
+        .into_iter()
+        .map(|item| sanitize_item(item, config.allow_iframes))
+        .collect());
 }
 
+// Articles are opened by link, so that's how ArticleView finds the item
 #[server]
-pub async fn get_feeds() -> Result<Vec<Feed>, ServerFnError> {
-    use crate::db::connect_db;
-    use sqlx::Row;
+pub async fn mark_item_read(feed_id: i64, link: String) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
 
-    let pool = connect_db().await;
+    let pool = use_pool()?;
+    sqlx::query("UPDATE items SET read = 1 WHERE feed_id = ? AND link = ?")
+        .bind(feed_id)
+        .bind(link)
+        .execute(&pool)
+        .await
+        .map_err(FeedError::from)?;
 
-    let feeds = sqlx::query_as::<_, Feed>("SELECT * FROM feeds")
-        .fetch_all(&pool)
-        .await?;
+    return Ok(());
+}
 
-    return Ok(feeds);
+#[server]
+pub async fn mark_all_read(feed_id: i64) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    sqlx::query("UPDATE items SET read = 1 WHERE feed_id = ?")
+        .bind(feed_id)
+        .execute(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    return Ok(());
 }
 
 #[server]
-pub async fn add_feed(url: String) -> Result<(), ServerFnError> {
-    use crate::db::connect_db;
+pub async fn set_item_starred(id: i64, starred: bool) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    sqlx::query("UPDATE items SET starred = ? WHERE id = ?")
+        .bind(starred)
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    return Ok(());
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn list_feeds(pool: &sqlx::SqlitePool) -> Result<Vec<Feed>, sqlx::Error> {
+    return sqlx::query_as::<_, Feed>("SELECT * FROM feeds")
+        .fetch_all(pool)
+        .await;
+}
+
+#[server]
+pub async fn get_feeds() -> Result<Vec<Feed>, ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    let feeds = list_feeds(&pool).await.map_err(FeedError::from)?;
+
+    return Ok(feeds);
+}
+
+// Whether another feed than `except` already has this URL
+#[cfg(feature = "ssr")]
+async fn is_subscribed(pool: &sqlx::SqlitePool, url: &str, except: Option<i64>) -> Result<bool, sqlx::Error> {
+    return sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM feeds WHERE url = ? AND id IS NOT ?)")
+        .bind(url)
+        .bind(except)
+        .fetch_one(pool)
+        .await;
+}
+
+// Stores the feed at `url`, or returns the feeds a website links to
+#[cfg(feature = "ssr")]
+pub(crate) async fn subscribe(pool: &sqlx::SqlitePool, config: &Config, url: String) -> Result<AddFeedResult, FeedError> {
+    use crate::discovery::discover_feeds;
+    use crate::syndication;
 
-    // TODO: display error message to user
-    if !is_valid_rss_feed(url.clone()).await {
-        return Err(ServerFnError::new("Invalid RSS feed"));
+    if !is_valid_url(url.clone()) {
+        return Err(FeedError::InvalidUrl(url));
+    }
+
+    if is_subscribed(pool, &url, None).await? {
+        return Err(FeedError::DuplicateFeed(url));
     }
 
-    let channel = match fetch_and_parse_rss(url.clone()).await {
+    let content = fetch_text(config, url.clone()).await?;
+
+    let channel = match syndication::parse(&content) {
         Ok(channel) => channel,
-        Err(err) => {
-            return Err(ServerFnError::new(format!(
-                "Error fetching RSS feed: {}",
-                err
-            )))
+        Err(_) => {
+            // Probably a website rather than a feed, offer the feeds it links to
+            let candidates = discover_feeds(config, &url, &content).await;
+            if candidates.is_empty() {
+                return Err(FeedError::NotAFeed(url));
+            }
+            return Ok(AddFeedResult::Candidates(candidates));
         }
     };
 
-    let pool = connect_db().await;
-    let _ = sqlx::query("INSERT INTO feeds (url, title, description) VALUES (?, ?, ?)")
+    let feed_id = sqlx::query("INSERT INTO feeds (url, title, description, refresh_interval) VALUES (?, ?, ?, ?)")
         .bind(url)
-        .bind(channel.title)
-        .bind(channel.description)
-        .execute(&pool)
-        .await;
+        .bind(channel.title.clone())
+        .bind(channel.description.clone())
+        .bind(config.refresh.default_interval_secs)
+        .execute(pool)
+        .await?
+        .last_insert_rowid();
 
-    return Ok(());
+    store_items(pool, feed_id, &channel).await?;
+
+    return Ok(AddFeedResult::Added);
 }
 
 #[server]
-pub async fn update_feed_info(id: i64) -> Result<(), ServerFnError> {
-    use crate::db::connect_db;
+pub async fn add_feed(url: String) -> Result<AddFeedResult, ServerFnError<FeedError>> {
+    use crate::config::use_config;
+    use crate::db::use_pool;
 
-    let pool = connect_db().await;
+    let config = use_config()?;
+    let pool = use_pool()?;
+
+    return Ok(subscribe(&pool, &config, url).await?);
+}
+
+#[server]
+pub async fn update_feed_info(id: i64) -> Result<(), ServerFnError<FeedError>> {
+    use crate::config::use_config;
+    use crate::db::use_pool;
+
+    let config = use_config()?;
+    let pool = use_pool()?;
 
     let feed = sqlx::query_as::<_, Feed>("SELECT * FROM feeds where id = ?")
         .bind(id)
         .fetch_one(&pool)
-        .await?;
+        .await
+        .map_err(FeedError::from)?;
 
-    let channel = match fetch_and_parse_rss(feed.url).await {
-        Ok(channel) => channel,
-        Err(err) => {
-            return Err(ServerFnError::new(format!(
-                "Error fetching RSS feed: {}",
-                err
-            )))
+    return Ok(refresh_feed(&pool, &config, &feed).await?);
+}
+
+// An empty title falls back to the upstream one. A new URL has to serve a feed before it
+// is stored, and the validators of the old URL are dropped along with its error state.
+#[server]
+pub async fn edit_feed(id: i64, custom_title: String, url: String, notes: String) -> Result<(), ServerFnError<FeedError>> {
+    use crate::config::use_config;
+    use crate::db::use_pool;
+
+    let config = use_config()?;
+    let pool = use_pool()?;
+
+    let feed = sqlx::query_as::<_, Feed>("SELECT * FROM feeds WHERE id = ?")
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    let url = url.trim().to_string();
+    if url != feed.url {
+        if !is_valid_url(url.clone()) {
+            return Err(FeedError::InvalidUrl(url).into());
+        }
+        if is_subscribed(&pool, &url, Some(id)).await.map_err(FeedError::from)? {
+            return Err(FeedError::DuplicateFeed(url).into());
+        }
+        if fetch_and_parse_feed(&config, url.clone()).await.is_err() {
+            return Err(FeedError::NotAFeed(url).into());
         }
-    };
 
-    let _ = sqlx::query("UPDATE feeds SET title = ?, description = ? WHERE id = ?")
-        .bind(channel.title)
-        .bind(channel.description)
+        sqlx::query(
+            "UPDATE feeds SET url = ?, etag = NULL, last_modified = NULL, last_error = NULL, failure_count = 0,
+                redirect_url = NULL, redirect_count = 0, dead = 0
+            WHERE id = ?",
+        )
+        .bind(&url)
         .bind(id)
         .execute(&pool)
-        .await;
+        .await
+        .map_err(FeedError::from)?;
+    }
+
+    let custom_title = Some(custom_title.trim().to_string()).filter(|title| !title.is_empty());
+    sqlx::query("UPDATE feeds SET custom_title = ?, notes = ? WHERE id = ?")
+        .bind(custom_title)
+        .bind(notes)
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(FeedError::from)?;
 
     return Ok(());
 }
 
 #[server]
-pub async fn delete_feed(id: i64) -> Result<(), ServerFnError> {
-    use crate::db::connect_db;
+pub async fn set_refresh_interval(id: i64, interval: i64) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    if interval <= 0 || interval > MAX_REFRESH_INTERVAL_SECS {
+        return Err(FeedError::InvalidInput("Refresh interval must be between 1 second and 30 days".to_string()).into());
+    }
 
-    let pool = connect_db().await;
-    let _ = sqlx::query("DELETE FROM feeds WHERE id = ?")
+    let pool = use_pool()?;
+    sqlx::query("UPDATE feeds SET refresh_interval = ? WHERE id = ?")
+        .bind(interval)
         .bind(id)
         .execute(&pool)
-        .await;
+        .await
+        .map_err(FeedError::from)?;
+
+    return Ok(());
+}
+
+// Returns false if there was no feed with this id
+#[cfg(feature = "ssr")]
+pub(crate) async fn remove_feed(pool: &sqlx::SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM feeds WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    return Ok(result.rows_affected() > 0);
+}
+
+#[server]
+pub async fn delete_feed(id: i64) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    if !remove_feed(&pool, id).await.map_err(FeedError::from)? {
+        return Err(FeedError::NotFound(format!("feed {}", id)).into());
+    }
 
     return Ok(());
 }
 
 #[component]
-fn FeedListItem(feed: Feed) -> impl IntoView {
-    let delete_feed = use_context::<Action<DeleteFeed, Result<(), ServerFnError>>>()
+fn FeedHealthBadge(feed: Feed) -> impl IntoView {
+    let (label, class, title) = if feed.dead {
+        ("Dead", "bg-red-100", feed.last_error.clone().unwrap_or_default())
+    } else if let Some(redirect_url) = &feed.redirect_url {
+        ("Moving", "bg-yellow-100", format!("Permanently redirects to {}", redirect_url))
+    } else if feed.failure_count > 0 {
+        ("Failing", "bg-yellow-100", feed.last_error.clone().unwrap_or_default())
+    } else if feed.last_success.is_none() {
+        ("New", "bg-slate-100", "Not refreshed yet".to_string())
+    } else {
+        ("OK", "bg-green-100", String::new())
+    };
+
+    view! {
+        <span class=format!("ml-2 px-2 rounded text-sm {}", class) title=title>{label}</span>
+    }
+}
+
+#[component]
+fn FeedListItem(feed: Feed, unread: i64) -> impl IntoView {
+    let delete_feed = use_context::<Action<DeleteFeed, Result<(), ServerFnError<FeedError>>>>()
         .expect("No delete feed action");
 
     let on_click = move |_| {
@@ -203,44 +741,199 @@ fn FeedListItem(feed: Feed) -> impl IntoView {
 
     view! {
         <li class="flex items-center my-2">
-            <a class="flex-1" href=format!("/feeds/{}", feed.id)>{feed.title}</a>
+            <a class="flex-1" href=format!("/feeds/{}", feed.id)>
+                {feed.display_title()}
+                <FeedHealthBadge feed=feed.clone() />
+                <Show when=move || unread != 0>
+                    <span class="ml-2 px-2 rounded-full text-sm bg-slate-100">{unread}</span>
+                </Show>
+            </a>
             <button class="p-2 ml-2 rounded bg-slate-100" on:click=on_click>Delete</button>
         </li>
     }
 }
 
 #[component]
-fn FeedList(feeds: Vec<Feed>) -> impl IntoView {
+fn FeedList(feeds: Vec<Feed>, unread_counts: Vec<(i64, i64)>) -> impl IntoView {
+    let unread = move |id: i64| {
+        unread_counts
+            .iter()
+            .find(|(feed_id, _)| *feed_id == id)
+            .map_or(0, |(_, count)| *count)
+    };
+
     view! {
         <ul>
             <For
                 each=move || feeds.clone()
                 key=|feed| feed.id
-                children=|feed| view! {
-                    <FeedListItem feed=feed />
+                children=move |feed| {
+                    let unread = unread(feed.id);
+                    view! {
+                        <FeedListItem feed=feed unread />
+                    }
                 }
             />
         </ul>
     }
 }
 
+#[component]
+fn CategorySection(
+    category: Category,
+    feeds: Vec<Feed>,
+    unread_counts: Vec<(i64, i64)>,
+) -> impl IntoView {
+    let delete_category = use_context::<Action<DeleteCategory, Result<(), ServerFnError<FeedError>>>>()
+        .expect("No delete category action");
+    let refresh_category = use_context::<Action<RefreshCategory, Result<(), ServerFnError<FeedError>>>>()
+        .expect("No refresh category action");
+
+    let id = category.id;
+
+    // Buttons inside <summary> would also toggle the section
+    let on_refresh = move |ev: ev::MouseEvent| {
+        ev.prevent_default();
+        refresh_category.dispatch(RefreshCategory { id });
+    };
+    let on_delete = move |ev: ev::MouseEvent| {
+        ev.prevent_default();
+        delete_category.dispatch(DeleteCategory { id });
+    };
+
+    view! {
+        <details class="my-4" open>
+            <summary class="flex items-center gap-2 cursor-pointer">
+                <span class="flex-1 font-bold">{category.name}</span>
+                <a class="text-sm" href=format!("/timeline?category={}", id)>Timeline</a>
+                <a class="text-sm" href=format!("/edition?category={}", id)>Edition</a>
+                <button class="p-1 rounded bg-slate-100 text-sm" on:click=on_refresh>
+                    Refresh
+                </button>
+                <button class="p-1 rounded bg-slate-100 text-sm" on:click=on_delete>
+                    Delete
+                </button>
+            </summary>
+            <FeedList feeds unread_counts />
+        </details>
+    }
+}
+
+// Feeds in several categories are listed in each of them
+#[component]
+fn GroupedFeedList(
+    feeds: Vec<Feed>,
+    unread_counts: Vec<(i64, i64)>,
+    categories: Vec<Category>,
+    memberships: Vec<(i64, i64)>,
+) -> impl IntoView {
+    let uncategorized = feeds
+        .iter()
+        .filter(|feed| !memberships.iter().any(|(feed_id, _)| *feed_id == feed.id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    view! {
+        {categories
+            .into_iter()
+            .map(|category| {
+                let category_feeds = feeds
+                    .iter()
+                    .filter(|feed| memberships.contains(&(feed.id, category.id)))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                view! {
+                    <CategorySection category feeds=category_feeds unread_counts=unread_counts.clone() />
+                }
+            })
+            .collect_view()}
+        {(!uncategorized.is_empty()).then(|| view! {
+            <details class="my-4" open>
+                <summary class="font-bold cursor-pointer">Uncategorized</summary>
+                <FeedList feeds=uncategorized unread_counts />
+            </details>
+        })}
+    }
+}
+
+#[component]
+fn FeedCandidateList(candidates: Vec<FeedCandidate>) -> impl IntoView {
+    let add_feed = use_context::<Action<AddFeed, Result<AddFeedResult, ServerFnError<FeedError>>>>()
+        .expect("No add feed action");
+
+    view! {
+        <div class="my-4">
+            <p>"This page isn't a feed, but it links to these:"</p>
+            <ul>
+                <For
+                    each=move || candidates.clone()
+                    key=|candidate| candidate.url.clone()
+                    children=move |candidate| {
+                        let url = candidate.url.clone();
+                        view! {
+                            <li class="flex items-center my-2">
+                                <span class="flex-1">
+                                    {candidate.title}
+                                    <span class="ml-2 text-sm text-gray-500">{candidate.url}</span>
+                                </span>
+                                <button
+                                    class="p-2 ml-2 rounded bg-slate-100"
+                                    on:click=move |_| add_feed.dispatch(AddFeed { url: url.clone() })
+                                >
+                                    Subscribe
+                                </button>
+                            </li>
+                        }
+                    }
+                />
+            </ul>
+        </div>
+    }
+}
+
 #[component]
 pub fn FeedListView() -> impl IntoView {
     let add_feed = create_server_action::<AddFeed>();
     let delete_feed = create_server_action::<DeleteFeed>();
+    let add_category = create_server_action::<AddCategory>();
+    let delete_category = create_server_action::<DeleteCategory>();
+    let refresh_category = create_server_action::<RefreshCategory>();
 
     let (error_message, set_error_message) = create_signal(String::new());
 
-    // Provide delete action to children
+    // Provide add and delete actions to children
+    provide_context(add_feed);
     provide_context(delete_feed);
+    provide_context(delete_category);
+    provide_context(refresh_category);
 
-    // Resource that fetches feeds from the server when either the
-    // add or delete feed actions are dispatched
+    // Resource that fetches feeds from the server whenever one of the
+    // feed or category actions is dispatched
     let feeds = create_resource(
-        move || (add_feed.version().get(), delete_feed.version().get()),
-        |_| async move { get_feeds().await.unwrap_or_default() },
+        move || (
+            add_feed.version().get(),
+            delete_feed.version().get(),
+            add_category.version().get(),
+            delete_category.version().get(),
+            refresh_category.version().get(),
+        ),
+        |_| async move {
+            let feeds = get_feeds().await?;
+            let unread_counts = get_unread_counts().await?;
+            let categories = get_categories().await?;
+            let memberships = get_feed_categories().await?;
+            Ok::<_, ServerFnError<FeedError>>((feeds, unread_counts, categories, memberships))
+        },
     );
 
+    let category_input: NodeRef<html::Input> = create_node_ref();
+
+    let on_add_category = move |_| {
+        let category_input = category_input().expect("<input> element should be mounted");
+        add_category.dispatch(AddCategory { name: category_input.value() });
+        category_input.set_value("");
+    };
+
     // Ref for the input element
     let input_element: NodeRef<html::Input> = create_node_ref();
 
@@ -268,10 +961,41 @@ pub fn FeedListView() -> impl IntoView {
                 <Show when=move || !error_message.get().is_empty()>
                     <p>{error_message.get()}</p>
                 </Show>
+                {move || match add_feed.value().get() {
+                    Some(Ok(AddFeedResult::Candidates(candidates))) => view! {
+                        <FeedCandidateList candidates />
+                    }.into_view(),
+                    Some(Err(err)) => view! { <p>{err.to_string()}</p> }.into_view(),
+                    _ => ().into_view(),
+                }}
+                <p class="my-4 flex gap-4">
+                    <a href="/edition">Build an edition</a>
+                    <a href="/timeline">Timeline</a>
+                    <a href="/starred">Starred</a>
+                    <a href="/profiles">Profiles</a>
+                    <a download href="/feeds.opml">Export OPML</a>
+                </p>
+                <form class="flex gap-2 my-4" action="/feeds/import" method="post" enctype="multipart/form-data">
+                    <input class="flex-1" type="file" name="file" accept=".opml,.xml,text/x-opml,text/xml" />
+                    <button class="p-2 rounded bg-slate-100" type="submit">Import OPML</button>
+                </form>
+                <div class="flex gap-2 my-4">
+                    <input class="p-2 rounded border flex-1" type="text" node_ref=category_input placeholder="New category" />
+                    <button class="p-2 rounded bg-slate-100" on:click=on_add_category>Add Category</button>
+                </div>
+                {move || match add_category.value().get() {
+                    Some(Err(err)) => view! { <p>{err.to_string()}</p> }.into_view(),
+                    _ => ().into_view(),
+                }}
+                <p class="text-sm">
+                    {move || if refresh_category.pending().get() { "Refreshing..." } else { "" }}
+                </p>
                 <Suspense fallback=|| view! { <p>Loading...</p> }>
-                    {move || feeds.get().map(|feeds| view! {
-                        <FeedList feeds />
-                    })}
+                    <ErrorBoundary fallback=|errors| view! { <ErrorTemplate errors /> }>
+                        {move || feeds.get().map(|feeds| feeds.map(|(feeds, unread_counts, categories, memberships)| view! {
+                            <GroupedFeedList feeds unread_counts categories memberships />
+                        }))}
+                    </ErrorBoundary>
                 </Suspense>
             </div>
         </Layout>
@@ -284,93 +1008,293 @@ pub struct FeedParams {
 }
 
 #[component]
-fn FeedDetailItem(item: Item, feed_id: i64) -> impl IntoView {
+pub fn FeedDetailItem(
+    item: FeedItem,
+    feed_id: i64,
+    // Shown when items of several feeds are listed together
+    #[prop(optional)] feed_title: Option<String>,
+) -> impl IntoView {
+    let set_starred = use_context::<Action<SetItemStarred, Result<(), ServerFnError<FeedError>>>>()
+        .expect("No set item starred action");
+
+    let link = item.link.clone().unwrap_or_default();
+    let item_id = item.id;
+    let starred = item.starred;
+
+    let on_star = move |_| {
+        set_starred.dispatch(SetItemStarred { id: item_id, starred: !starred });
+    };
+
     return view! {
-        <section class="p-4 my-4 border shadow-lg">
+        <section class="p-4 my-4 border shadow-lg" class:font-semibold=!item.read>
             <p class="text-lg">
-                <a href=format!(
-                    "/article?url={}&feed_id={}",
-                    item.link.clone().unwrap(),
-                    feed_id
-                )>
+                <a href=article_href("/article", &link, feed_id)>
                     {item.title.clone()}
                 </a>
             </p>
             <p class="text-sm mb-2">
+                {feed_title.map(|feed_title| view! {
+                    <a class="mr-2" href=format!("/feeds/{}", feed_id)>{feed_title}</a>
+                })}
                 <span class="mr-2">
                     <FormattedDate date_string=item.pub_date.clone().unwrap_or_default() />
                 </span>
-                <a class="mr-2" href=item.link.clone()>Read Original</a>
-                <a download href=format!("/article/pdf?url={}", item.link.unwrap())>Download as PDF</a>
+                <a class="mr-2" href=link.clone()>Read Original</a>
+                <a class="mr-2" download href=article_href("/article/pdf", &link, feed_id)>Download as PDF</a>
+                <a class="mr-2" download href=article_href("/article/epub", &link, feed_id)>Download as EPUB</a>
+                <button on:click=on_star>{if starred { "Unstar" } else { "Star" }}</button>
             </p>
             <div inner_html=item.description.clone()></div>
         </section>
     };
 }
 
+const REFRESH_INTERVALS: [(i64, &str); 6] = [
+    (15 * 60, "Every 15 minutes"),
+    (60 * 60, "Every hour"),
+    (3 * 60 * 60, "Every 3 hours"),
+    (6 * 60 * 60, "Every 6 hours"),
+    (12 * 60 * 60, "Every 12 hours"),
+    (24 * 60 * 60, "Every day"),
+];
+
+#[component]
+fn FeedRefreshSettings(feed: Feed) -> impl IntoView {
+    let set_interval = create_server_action::<SetRefreshInterval>();
+    let feed_id = feed.id;
+    let current_interval = feed.refresh_interval;
+
+    let on_change = move |ev| {
+        if let Ok(interval) = event_target_value(&ev).parse::<i64>() {
+            set_interval.dispatch(SetRefreshInterval { id: feed_id, interval });
+        }
+    };
+
+    view! {
+        <div class="text-sm my-2 flex items-center gap-4">
+            <select class="p-1 rounded border" on:change=on_change>
+                {REFRESH_INTERVALS
+                    .iter()
+                    .map(|(interval, label)| view! {
+                        <option value=interval.to_string() selected={*interval == current_interval}>{*label}</option>
+                    })
+                    .collect_view()}
+            </select>
+            <span>
+                "Last refreshed: "
+                {match feed.last_success.clone() {
+                    Some(date) => view! { <FormattedDate date_string=date /> }.into_view(),
+                    None => "never".into_view(),
+                }}
+            </span>
+            {feed.last_error.clone().map(|err| view! {
+                <span class="text-red-600" title=err.clone()>
+                    {format!("Failing ({} attempts)", feed.failure_count)}
+                </span>
+            })}
+        </div>
+    }
+}
+
+#[component]
+fn FeedEditForm(feed: Feed) -> impl IntoView {
+    let edit_feed = use_context::<Action<EditFeed, Result<(), ServerFnError<FeedError>>>>()
+        .expect("No edit feed action");
+
+    view! {
+        <details class="my-2">
+            <summary class="text-sm cursor-pointer">Edit feed</summary>
+            <ActionForm action=edit_feed class="flex flex-col gap-2 my-2 max-w-[700px]">
+                <input type="hidden" name="id" value=feed.id.to_string() />
+                <label class="flex flex-col text-sm">
+                    "Title"
+                    <input
+                        class="p-2 rounded border"
+                        type="text"
+                        name="custom_title"
+                        value=feed.custom_title.clone().unwrap_or_default()
+                        placeholder=feed.title.clone()
+                    />
+                </label>
+                <label class="flex flex-col text-sm">
+                    "URL"
+                    <input class="p-2 rounded border" type="url" name="url" value=feed.url.clone() />
+                </label>
+                <label class="flex flex-col text-sm">
+                    "Notes"
+                    <textarea class="p-2 rounded border" name="notes" rows="3">{feed.notes.clone()}</textarea>
+                </label>
+                <div class="flex items-center gap-2">
+                    <button class="p-2 rounded bg-slate-100" type="submit">Save</button>
+                    {move || match edit_feed.value().get() {
+                        Some(Err(err)) => view! { <span class="text-red-600">{err.to_string()}</span> }.into_view(),
+                        _ => ().into_view(),
+                    }}
+                </div>
+            </ActionForm>
+        </details>
+    }
+}
+
 #[component]
 pub fn FeedDetailView() -> impl IntoView {
     let params = use_params::<FeedParams>();
 
+    // Refreshing is left to the scheduler, the page only fetches the feed out of turn when
+    // asked to. The outcome is recorded like a scheduled refresh, a dead feed comes back to life.
+    let update_feed = create_server_action::<UpdateFeedInfo>();
+    let edit_feed = create_server_action::<EditFeed>();
+    let mark_all_read = create_server_action::<MarkAllRead>();
+    let set_starred = create_server_action::<SetItemStarred>();
+
+    provide_context(edit_feed);
+    provide_context(set_starred);
+
+    let id = move || params.with(|params| params.as_ref().ok().map(|params| params.id));
+
     let feed = create_resource(
-        move || params.get().unwrap().id,
-        |id| async move { get_feed(id).await.unwrap() },
+        move || (id(), update_feed.version().get(), edit_feed.version().get()),
+        |(id, _, _)| async move {
+            match id {
+                Some(id) => get_feed(id).await,
+                None => Err(FeedError::NotFound("feed".to_string()).into()),
+            }
+        },
     );
 
-    let channel = create_resource(
-        move || params.get().unwrap().id,
-        |id| async move { get_channel(id).await.unwrap() },
+    // Items are served from the database, refreshing only adds to them
+    let items = create_resource(
+        move || (
+            id(),
+            update_feed.version().get(),
+            mark_all_read.version().get(),
+            set_starred.version().get(),
+        ),
+        |(id, _, _, _)| async move {
+            match id {
+                Some(id) => get_feed_items(id).await,
+                None => Ok(Vec::new()),
+            }
+        },
     );
 
-    params.with(|p| {
-        update_feed_info(p.clone().unwrap().id);
-    });
-
     view! {
         <Suspense fallback=|| view! {
             <Layout headline="Feed Details".to_string()>
                 <p>Loading...</p>
             </Layout>
         }>
-            {move || feed.get().map(|feed| {
-                let feed_id = feed.id.clone();
-                view! {
-                    <Layout headline=feed.title.clone()>
-                        <BreadCrumbs items=vec![
-                            BreadCrumbItem { text: "Feeds".to_string(), url: "/feeds".to_string() },
-                            BreadCrumbItem { text: feed.title.clone(), url: format!("/feeds/{}", feed.id) },
-                        ] />
-                        <Suspense fallback=|| view! {
-                            <For
-                                each=move || (1..6)
-                                key=|i| i.clone()
-                                children=|_| view! {
-                                    <section class="p-4 my-4 border shadow-lg flex flex-col">
-                                        <p class="w-[80ch] my-0.5 h-6 rounded bg-slate-100 animate-pulse" />
-                                        <div class="mb-2 text-sm flex">
-                                            <div class="w-[13ch] mr-2 my-0.5 h-4 rounded bg-slate-100 animate-pulse" />
-                                            <div class="w-[10ch] mr-2 my-0.5 h-4 rounded bg-slate-100 animate-pulse" />
-                                            <div class="w-[12ch] mr-2 my-0.5 h-4 rounded bg-slate-100 animate-pulse" />
-                                        </div>
-                                        <p class="w-[72ch] my-0.5 h-5 rounded bg-slate-100 animate-pulse" />
-                                        <p class="w-[50ch] my-0.5 h-5 rounded bg-slate-100 animate-pulse" />
-                                    </section>
-                                }
-                            />
-                        }>
-                            {move || channel.get().map(|channel| view! {
+            <ErrorBoundary fallback=|errors| view! {
+                <Layout headline="Feed Details".to_string()>
+                    <ErrorTemplate errors />
+                </Layout>
+            }>
+                {move || feed.get().map(|feed| feed.map(|feed| {
+                    let feed_id = feed.id.clone();
+                    view! {
+                        <Layout headline=feed.display_title()>
+                            <BreadCrumbs items=vec![
+                                BreadCrumbItem { text: "Feeds".to_string(), url: "/feeds".to_string() },
+                                BreadCrumbItem { text: feed.display_title(), url: format!("/feeds/{}", feed.id) },
+                            ] />
+                            <FeedRefreshSettings feed=feed.clone() />
+                            <FeedCategorySettings feed_id=feed.id />
+                            {(!feed.notes.is_empty()).then(|| view! {
+                                <p class="text-sm my-2 whitespace-pre-line">{feed.notes.clone()}</p>
+                            })}
+                            <FeedEditForm feed=feed.clone() />
+                            <p class="text-sm my-2 flex items-center gap-4">
+                                <button
+                                    class="p-1 rounded bg-slate-100"
+                                    on:click=move |_| mark_all_read.dispatch(MarkAllRead { feed_id })
+                                >
+                                    Mark all read
+                                </button>
+                                <button
+                                    class="p-1 rounded bg-slate-100"
+                                    on:click=move |_| update_feed.dispatch(UpdateFeedInfo { id: feed_id })
+                                >
+                                    Refresh now
+                                </button>
+                                {move || if update_feed.pending().get() { "Refreshing..." } else { "" }}
+                                {move || match update_feed.value().get() {
+                                    Some(Err(err)) => view! { <span class="text-red-600">{err.to_string()}</span> }.into_view(),
+                                    _ => ().into_view(),
+                                }}
+                            </p>
+                            <Suspense fallback=|| view! {
                                 <For
-                                    each=move || channel.items.clone()
-                                    key=|item| item.link.clone()
-                                    children=move |item| view! {
-                                        <FeedDetailItem item feed_id=feed_id.clone() />
+                                    each=move || (1..6)
+                                    key=|i| i.clone()
+                                    children=|_| view! {
+                                        <section class="p-4 my-4 border shadow-lg flex flex-col">
+                                            <p class="w-[80ch] my-0.5 h-6 rounded bg-slate-100 animate-pulse" />
+                                            <div class="mb-2 text-sm flex">
+                                                <div class="w-[13ch] mr-2 my-0.5 h-4 rounded bg-slate-100 animate-pulse" />
+                                                <div class="w-[10ch] mr-2 my-0.5 h-4 rounded bg-slate-100 animate-pulse" />
+                                                <div class="w-[12ch] mr-2 my-0.5 h-4 rounded bg-slate-100 animate-pulse" />
+                                            </div>
+                                            <p class="w-[72ch] my-0.5 h-5 rounded bg-slate-100 animate-pulse" />
+                                            <p class="w-[50ch] my-0.5 h-5 rounded bg-slate-100 animate-pulse" />
+                                        </section>
                                     }
                                 />
-                            })}
-                        </Suspense>
-                    </Layout>
-                }
-            })}
+                            }>
+                                <ErrorBoundary fallback=|errors| view! { <ErrorTemplate errors /> }>
+                                    {move || items.get().map(|items| items.map(|items| view! {
+                                        <For
+                                            each=move || items.clone()
+                                            key=|item| item.id
+                                            children=move |item| view! {
+                                                <FeedDetailItem item feed_id=feed_id.clone() />
+                                            }
+                                        />
+                                    }))}
+                                </ErrorBoundary>
+                            </Suspense>
+                        </Layout>
+                    }
+                }))}
+            </ErrorBoundary>
         </Suspense>
     }
 }
+
+#[component]
+pub fn StarredView() -> impl IntoView {
+    let set_starred = create_server_action::<SetItemStarred>();
+
+    provide_context(set_starred);
+
+    let items = create_resource(
+        move || set_starred.version().get(),
+        |_| async move { get_starred_items().await },
+    );
+
+    view! {
+        <Layout headline="Starred".to_string()>
+            <BreadCrumbs items=vec![
+                BreadCrumbItem { text: "Feeds".to_string(), url: "/feeds".to_string() },
+                BreadCrumbItem { text: "Starred".to_string(), url: "/starred".to_string() },
+            ] />
+            <Suspense fallback=|| view! { <p>Loading...</p> }>
+                <ErrorBoundary fallback=|errors| view! { <ErrorTemplate errors /> }>
+                    {move || items.get().map(|items| items.map(|items| if items.is_empty() {
+                        view! { <p>No starred items yet.</p> }.into_view()
+                    } else {
+                        view! {
+                            <For
+                                each=move || items.clone()
+                                key=|item| item.id
+                                children=move |item| {
+                                    let feed_id = item.feed_id;
+                                    view! { <FeedDetailItem item feed_id /> }
+                                }
+                            />
+                        }.into_view()
+                    }))}
+                </ErrorBoundary>
+            </Suspense>
+        </Layout>
+    }
+}
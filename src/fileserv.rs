@@ -1,6 +1,6 @@
 use axum::{
     body::Body,
-    extract::State,
+    extract::{Extension, State},
     response::IntoResponse,
     http::{Request, Response, StatusCode, Uri},
 };
@@ -9,15 +9,30 @@ use tower::ServiceExt;
 use tower_http::services::ServeDir;
 use leptos::*;
 use crate::app::App;
+use crate::config::Config;
+use sqlx::SqlitePool;
 
-pub async fn file_and_error_handler(uri: Uri, State(options): State<LeptosOptions>, req: Request<Body>) -> AxumResponse {
+pub async fn file_and_error_handler(
+    uri: Uri,
+    State(options): State<LeptosOptions>,
+    Extension(config): Extension<Config>,
+    Extension(pool): Extension<SqlitePool>,
+    req: Request<Body>,
+) -> AxumResponse {
     let root = options.site_root.clone();
     let res = get_static_file(uri.clone(), &root).await.unwrap();
 
     if res.status() == StatusCode::OK {
         res.into_response()
     } else {
-        let handler = leptos_axum::render_app_to_stream(options.to_owned(), App);
+        let handler = leptos_axum::render_app_to_stream_with_context(
+            options.to_owned(),
+            move || {
+                provide_context(config.clone());
+                provide_context(pool.clone());
+            },
+            App,
+        );
         handler(req).await.into_response()
     }
 }
@@ -0,0 +1,312 @@
+use crate::config::Config;
+use crate::newspaper::PageLayout;
+use crate::profiles::Profile;
+use image::{DynamicImage, GrayImage, ImageFormat, Luma};
+use leptos::logging;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Cursor;
+use url::Url;
+
+// Resolution of embedded images on paper, profiles set their own
+pub const DPI: f64 = 150.0;
+
+// Larger images are left out
+const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+// Images downloaded at the same time, for an article or one article of an edition
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+// Shades of gray of e-ink screens
+const GRAY_LEVELS: usize = 16;
+
+// E-ink screens show photos paler than paper does
+const EINK_CONTRAST: f32 = 20.0;
+
+// Images are downscaled to fit a column of the page at the profile's resolution
+#[derive(Clone, Copy, Debug)]
+pub struct ImageOptions {
+    pub max_width: u32,
+    pub max_height: u32,
+    pub grayscale: bool,
+    pub dither: bool,
+}
+
+impl ImageOptions {
+    // Without a profile, the text area of an A4 page with 2cm margins
+    pub fn new(profile: Option<&Profile>, layout: PageLayout) -> ImageOptions {
+        let dpi = profile.map(|profile| profile.dpi).unwrap_or(DPI);
+        let (width_mm, height_mm, profile_columns) = match profile {
+            Some(profile) => (
+                profile.page_width_mm - 2.0 * profile.margin_mm,
+                profile.page_height_mm - 2.0 * profile.margin_mm,
+                profile.columns,
+            ),
+            None => (170.0, 257.0, 1),
+        };
+        let columns = match layout {
+            PageLayout::Standard => profile_columns.max(1) as f64,
+            PageLayout::Newspaper { columns } => columns as f64,
+        };
+
+        return ImageOptions {
+            max_width: mm_to_px(width_mm / columns, dpi),
+            max_height: mm_to_px(height_mm, dpi),
+            grayscale: profile.is_some_and(Profile::grayscale),
+            dither: profile.is_some_and(Profile::dither),
+        };
+    }
+}
+
+fn mm_to_px(mm: f64, dpi: f64) -> u32 {
+    return (mm / 25.4 * dpi).round().max(1.0) as u32;
+}
+
+// An image of a data URI produced by embed_images
+pub struct EmbeddedImage {
+    pub extension: &'static str,
+    pub bytes: Vec<u8>,
+    pub width: u32,
+}
+
+impl EmbeddedImage {
+    pub fn from_data_uri(uri: &str) -> Option<EmbeddedImage> {
+        use base64::Engine;
+
+        let (mime, data) = uri.strip_prefix("data:")?.split_once(";base64,")?;
+        let extension = match mime {
+            "image/png" => "png",
+            "image/jpeg" => "jpg",
+            _ => return None,
+        };
+        let bytes = base64::engine::general_purpose::STANDARD.decode(data).ok()?;
+        let (width, _) = image::ImageReader::new(Cursor::new(&bytes))
+            .with_guessed_format()
+            .ok()?
+            .into_dimensions()
+            .ok()?;
+
+        return Some(EmbeddedImage { extension, bytes, width });
+    }
+}
+
+// Downloads the images of sanitized HTML and replaces their URLs with data URIs, so
+// renderers neither fetch them on their own nor choke on formats they don't read.
+// Relative URLs are resolved against the article. Images that can't be embedded are left out.
+pub async fn embed_images(config: &Config, html: &str, base_url: &str, options: ImageOptions) -> String {
+    let base = Url::parse(base_url).ok();
+    let client = config.http_client();
+    let permits = std::sync::Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_DOWNLOADS));
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for source in image_sources(html) {
+        let url = match &base {
+            Some(base) => base.join(&source),
+            None => Url::parse(&source),
+        };
+        let client = client.clone();
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let result = match url {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => embed_image(client, url, options).await,
+                Ok(url) => Err(format!("Unsupported URL scheme {}", url.scheme())),
+                Err(e) => Err(e.to_string()),
+            };
+            (source, result)
+        });
+    }
+
+    let mut embedded = HashMap::new();
+    while let Some(task) = tasks.join_next().await {
+        match task {
+            Ok((source, Ok(uri))) => {
+                embedded.insert(source, uri);
+            }
+            Ok((source, Err(err))) => logging::warn!("Leaving out image {}: {}", source, err),
+            Err(err) => logging::warn!("Error embedding image: {}", err),
+        }
+    }
+
+    return replace_images(html, &embedded);
+}
+
+fn image_sources(html: &str) -> Vec<String> {
+    use scraper::{Html, Selector};
+
+    let fragment = Html::parse_fragment(html);
+    let selector = Selector::parse("img[src]").unwrap();
+
+    let mut sources = fragment
+        .select(&selector)
+        .filter_map(|img| img.value().attr("src"))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    sources.sort();
+    sources.dedup();
+    return sources;
+}
+
+async fn embed_image(client: reqwest::Client, url: Url, options: ImageOptions) -> Result<String, String> {
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    if response.content_length().is_some_and(|length| length > MAX_IMAGE_BYTES) {
+        return Err("Image too large".to_string());
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(str::to_string);
+    // Content-Length can be missing or wrong, so the limit is checked while reading
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if (bytes.len() + chunk.len()) as u64 > MAX_IMAGE_BYTES {
+            return Err("Image too large".to_string());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    // Decoding and scaling are CPU bound
+    return tokio::task::spawn_blocking(move || {
+        let mut image = scale(decode(&bytes, content_type.as_deref(), options)?, options);
+        if options.grayscale {
+            image = DynamicImage::ImageLuma8(eink(&image, options.dither));
+        }
+        return data_uri(&image, options.dither);
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+}
+
+// PNG, JPEG, GIF and WebP are decoded by the image crate, SVG is rasterized by resvg
+fn decode(bytes: &[u8], content_type: Option<&str>, options: ImageOptions) -> Result<DynamicImage, String> {
+    let svg = content_type.is_some_and(|content_type| content_type.starts_with("image/svg"))
+        || (image::guess_format(bytes).is_err() && bytes.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'<'));
+    if svg {
+        return rasterize_svg(bytes, options);
+    }
+
+    return image::load_from_memory(bytes).map_err(|e| e.to_string());
+}
+
+// Rendered at the size that fits the page, small icons are enlarged at most twice
+fn rasterize_svg(bytes: &[u8], options: ImageOptions) -> Result<DynamicImage, String> {
+    use resvg::{tiny_skia, usvg};
+
+    let tree = usvg::Tree::from_data(bytes, &usvg::Options::default()).map_err(|e| e.to_string())?;
+    let size = tree.size();
+    let scale = (options.max_width as f32 / size.width())
+        .min(options.max_height as f32 / size.height())
+        .min(2.0);
+
+    let width = (size.width() * scale).ceil() as u32;
+    let height = (size.height() * scale).ceil() as u32;
+    let mut pixmap = tiny_skia::Pixmap::new(width.max(1), height.max(1)).ok_or("SVG has no size")?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    let png = pixmap.encode_png().map_err(|e| e.to_string())?;
+    return image::load_from_memory_with_format(&png, ImageFormat::Png).map_err(|e| e.to_string());
+}
+
+fn scale(image: DynamicImage, options: ImageOptions) -> DynamicImage {
+    use image::imageops::FilterType;
+
+    if image.width() <= options.max_width && image.height() <= options.max_height {
+        return image;
+    }
+    return image.resize(options.max_width, options.max_height, FilterType::Lanczos3);
+}
+
+// Floyd–Steinberg dithering to evenly spaced shades of gray
+struct GrayLevels(usize);
+
+impl image::imageops::ColorMap for GrayLevels {
+    type Color = Luma<u8>;
+
+    fn index_of(&self, color: &Luma<u8>) -> usize {
+        return (color.0[0] as usize * (self.0 - 1) + 127) / 255;
+    }
+
+    fn map_color(&self, color: &mut Luma<u8>) {
+        color.0[0] = (self.index_of(color) * 255 / (self.0 - 1)) as u8;
+    }
+}
+
+// Grayscale with more contrast, transparent areas become white like the page behind them
+fn eink(image: &DynamicImage, dither: bool) -> GrayImage {
+    let rgba = image.to_rgba8();
+    let gray = GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let luma = 0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32;
+        let alpha = a as f32 / 255.0;
+        return Luma([(luma * alpha + 255.0 * (1.0 - alpha)).round() as u8]);
+    });
+
+    let mut gray = image::imageops::contrast(&gray, EINK_CONTRAST);
+    if dither {
+        image::imageops::dither(&mut gray, &GrayLevels(GRAY_LEVELS));
+    }
+    return gray;
+}
+
+// Photos become JPEGs, images with transparency and dithered ones PNGs,
+// since JPEG compression would smear the dither pattern
+fn data_uri(image: &DynamicImage, dithered: bool) -> Result<String, String> {
+    use base64::Engine;
+    use image::codecs::jpeg::JpegEncoder;
+
+    let mut bytes = Vec::new();
+    let mime = if image.color().has_alpha() || dithered {
+        image
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .map_err(|e| e.to_string())?;
+        "image/png"
+    } else {
+        let encoded = match image {
+            DynamicImage::ImageLuma8(gray) => JpegEncoder::new_with_quality(&mut bytes, 85).encode_image(gray),
+            image => JpegEncoder::new_with_quality(&mut bytes, 85).encode_image(&image.to_rgb8()),
+        };
+        encoded.map_err(|e| e.to_string())?;
+        "image/jpeg"
+    };
+
+    return Ok(format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes)));
+}
+
+// The HTML went through sanitize_html already, so this keeps what that lets through
+fn replace_images(html: &str, embedded: &HashMap<String, String>) -> String {
+    use ammonia::Builder;
+    use scraper::{Html, Selector};
+
+    let mut fragment = Html::parse_fragment(html);
+    let selector = Selector::parse("img").unwrap();
+    let missing = fragment
+        .select(&selector)
+        .filter(|img| !img.value().attr("src").is_some_and(|src| embedded.contains_key(src)))
+        .map(|img| img.id())
+        .collect::<Vec<_>>();
+    for id in missing {
+        if let Some(mut node) = fragment.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+    let html = fragment.root_element().inner_html();
+
+    let mut builder = Builder::default();
+    builder
+        .add_url_schemes(&["data"])
+        .add_tags(&["iframe"])
+        .add_tag_attributes("iframe", &["src", "width", "height", "title", "allowfullscreen"])
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            ("img", "src") => embedded.get(value).map(|uri| Cow::Owned(uri.clone())),
+            _ => Some(Cow::Borrowed(value)),
+        });
+
+    return builder.clean(&html).to_string();
+}
@@ -1,5 +1,6 @@
 use leptos::*;
 use leptos_meta::*;
+use leptos_router::*;
 
 #[component]
 pub fn Layout(headline: String, children: Children) -> impl IntoView {
@@ -8,6 +9,9 @@ pub fn Layout(headline: String, children: Children) -> impl IntoView {
         <nav class="fixed w-full top-0 left-0 h-16 bg-white shadow-lg">
             <div class="ml-4 lg:ml-16 flex items-center justify-between h-full">
                 <h1 class="text-2xl font-bold">{headline.clone()}</h1>
+                <Form method="GET" action="/search" class="mr-4 lg:mr-16">
+                    <input class="p-2 rounded border" type="search" name="q" placeholder="Search" />
+                </Form>
             </div>
         </nav>
         <main class="mt-20 px-4 lg:px-16">
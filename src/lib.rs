@@ -1,17 +1,51 @@
 pub mod app;
 pub mod error_template;
+pub mod errors;
 pub mod feeds;
 pub mod article;
+pub mod edition;
 pub mod layout;
 pub mod breadcrumbs;
 pub mod date;
+pub mod syndication;
+pub mod categories;
+pub mod timeline;
+pub mod search;
+pub mod profiles;
 
 #[cfg(feature = "ssr")]
 pub mod fileserv;
 
+#[cfg(feature = "ssr")]
+pub mod cli;
+
+#[cfg(feature = "ssr")]
+pub mod config;
+
 #[cfg(feature = "ssr")]
 pub mod db;
 
+#[cfg(feature = "ssr")]
+pub mod scheduler;
+
+#[cfg(feature = "ssr")]
+pub mod discovery;
+
+#[cfg(feature = "ssr")]
+pub mod opml;
+
+#[cfg(feature = "ssr")]
+pub mod sanitize;
+
+#[cfg(feature = "ssr")]
+pub mod render;
+
+#[cfg(feature = "ssr")]
+pub mod newspaper;
+
+#[cfg(feature = "ssr")]
+pub mod images;
+
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
 pub fn hydrate() {
@@ -1,16 +1,45 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
-    use axum::Router;
-    use axum::routing::get;
+    use axum::{Extension, Router};
+    use axum::routing::{get, post};
+    use clap::Parser;
     use leptos::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
     use rss_newspaper_generator::app::*;
+    use rss_newspaper_generator::cli::{self, Cli};
+    use rss_newspaper_generator::config::Config;
     use rss_newspaper_generator::fileserv::file_and_error_handler;
     use rss_newspaper_generator::db::connect_db;
-    use rss_newspaper_generator::article::get_article_pdf;
+    use rss_newspaper_generator::article::{get_article_epub, get_article_pdf};
+    use rss_newspaper_generator::edition::{get_edition_epub, get_edition_pdf};
+    use rss_newspaper_generator::opml::{get_opml_export, post_opml_import};
+    use rss_newspaper_generator::scheduler;
 
-    let _ = connect_db().await;
+    let cli = Cli::parse();
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error loading configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // One pool for the whole process, shared by server functions, handlers and the scheduler
+    let pool = connect_db(&config).await;
+
+    // A subcommand runs headless and exits, otherwise we start the server
+    if let Some(command) = cli.command {
+        if let Err(e) = cli::run(&pool, &config, command).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Keep feeds fresh in the background, independent of page views
+    tokio::spawn(scheduler::run(pool.clone(), config.clone()));
 
     // Setting get_configuration(None) means we'll be using cargo-leptos's env values
     // For deployment these variables are:
@@ -24,10 +53,29 @@ async fn main() {
 
     // build our application with a route
     let app = Router::new()
-        .leptos_routes(&leptos_options, routes, App)
+        .leptos_routes_with_context(
+            &leptos_options,
+            routes,
+            {
+                let config = config.clone();
+                let pool = pool.clone();
+                move || {
+                    provide_context(config.clone());
+                    provide_context(pool.clone());
+                }
+            },
+            App,
+        )
         .fallback(file_and_error_handler)
         .with_state(leptos_options)
-        .route("/article/pdf", get(get_article_pdf));
+        .route("/article/pdf", get(get_article_pdf))
+        .route("/article/epub", get(get_article_epub))
+        .route("/edition/pdf", get(get_edition_pdf))
+        .route("/edition/epub", get(get_edition_epub))
+        .route("/feeds.opml", get(get_opml_export))
+        .route("/feeds/import", post(post_opml_import))
+        .layer(Extension(config))
+        .layer(Extension(pool));
 
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
     logging::log!("listening on http://{}", &addr);
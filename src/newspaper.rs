@@ -0,0 +1,190 @@
+use crate::sanitize::escape_text;
+
+pub const DEFAULT_COLUMNS: u8 = 3;
+
+// Name in the masthead when a document doesn't come from a single feed
+pub const MASTHEAD: &str = "RSS Newspaper";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PageLayout {
+    // Single column, as pandoc lays out documents by default
+    #[default]
+    Standard,
+    // Masthead, page headers and footers, and article bodies set in columns
+    Newspaper { columns: u8 },
+}
+
+impl PageLayout {
+    // From the `layout` and `columns` parameters of a download or the command line
+    pub fn parse(name: Option<&str>, columns: Option<u8>) -> Result<Layout, String> {
+        match name.unwrap_or("standard") {
+            "standard" => Ok(PageLayout::Standard),
+            "newspaper" => Ok(PageLayout::Newspaper { columns: columns.unwrap_or(DEFAULT_COLUMNS).clamp(2, 3) }),
+            other => Err(format!("Unknown layout: {}", other)),
+        }
+    }
+}
+
+// A sentence from the middle of the article that is long enough to be worth quoting
+pub fn pull_quote(text: &str) -> Option<String> {
+    let sentences = text
+        .split_inclusive(|c| matches!(c, '.' | '!' | '?'))
+        .map(str::trim)
+        .filter(|sentence| (60..=180).contains(&sentence.chars().count()))
+        .collect::<Vec<_>>();
+
+    return sentences.get(sentences.len() / 2).map(|sentence| sentence.to_string());
+}
+
+// The pull quote goes after the second paragraph, short articles go without one
+fn insert_pull_quote(content: &str, quote: &str) -> String {
+    let Some((index, _)) = content.match_indices("</p>").nth(1) else {
+        return content.to_string();
+    };
+    let index = index + "</p>".len();
+
+    return format!(
+        "{}<div class=\"pull-quote\"><p>{}</p></div>{}",
+        &content[..index],
+        escape_text(quote),
+        &content[index..]
+    );
+}
+
+// Headline, byline, standfirst and body of an article, marked up with the classes the
+// renderers lay out. `content` has to be sanitized already, `text` is its plain text.
+pub fn format_article(level: u8, headline: &str, byline: &str, standfirst: &str, text: &str, content: &str) -> String {
+    let body = match pull_quote(text) {
+        Some(quote) => insert_pull_quote(content, &quote),
+        None => content.to_string(),
+    };
+
+    let mut html = format!("<h{}>{}</h{}>", level, escape_text(headline), level);
+    if !byline.is_empty() {
+        html.push_str(&format!("<div class=\"byline\"><p>{}</p></div>", escape_text(byline)));
+    }
+    if !standfirst.is_empty() {
+        html.push_str(&format!("<p class=\"italic\">{}</p>", escape_text(standfirst)));
+    }
+    html.push_str(&format!("<div class=\"article-body\">{}</div>", body));
+
+    return html;
+}
+
+// Date and edition number below the masthead
+pub fn dateline(date: &chrono::DateTime<chrono::Local>, number: Option<i64>) -> String {
+    let date = date.format("%A, %d %B %Y").to_string();
+    match number {
+        Some(number) => format!("{} · No. {}", date, number),
+        None => date,
+    }
+}
+
+pub fn latex_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+// Passed to pandoc as header-includes for LaTeX engines
+pub fn latex_preamble(masthead: &str, dateline: &str) -> String {
+    format!(
+        "\\usepackage{{multicol}}
+\\usepackage{{fancyhdr}}
+\\pagestyle{{fancy}}
+\\fancyhf{{}}
+\\fancyhead[L]{{\\small\\scshape {}}}
+\\fancyhead[R]{{\\small {}}}
+\\fancyfoot[C]{{\\small\\thepage}}
+\\setlength{{\\columnsep}}{{1.5em}}
+\\setlength{{\\columnseprule}}{{0.4pt}}",
+        latex_escape(masthead),
+        latex_escape(dateline)
+    )
+}
+
+// Passed to pandoc as include-before, so it replaces the title block
+pub fn latex_masthead(masthead: &str, dateline: &str) -> String {
+    format!(
+        "\\thispagestyle{{empty}}
+\\begin{{center}}
+{{\\fontsize{{40}}{{44}}\\selectfont\\bfseries {}}}\\par
+\\vspace{{0.5em}}\\hrule\\vspace{{0.4em}}
+{{\\small {}}}\\par
+\\vspace{{0.4em}}\\hrule
+\\end{{center}}",
+        latex_escape(masthead),
+        latex_escape(dateline)
+    )
+}
+
+// Turns the marked up divs into LaTeX, the classes of HTML output are styled by NEWSPAPER_CSS
+pub const NEWSPAPER_FILTER: &str = r#"
+local columns = '3'
+
+local function wrap(el, before, after)
+  local blocks = pandoc.List({ pandoc.RawBlock('latex', before) })
+  blocks:extend(el.content)
+  blocks:insert(pandoc.RawBlock('latex', after))
+  return blocks
+end
+
+local function div(el)
+  if not FORMAT:match('latex') then
+    return nil
+  end
+  if el.classes:includes('article-body') then
+    return wrap(el, '\\begin{multicols}{' .. columns .. '}', '\\end{multicols}')
+  elseif el.classes:includes('pull-quote') then
+    return wrap(el, '\\begin{center}\\rule{0.8\\linewidth}{0.4pt}\\par\\large\\itshape', '\\par\\rule{0.8\\linewidth}{0.4pt}\\end{center}')
+  elseif el.classes:includes('byline') then
+    return wrap(el, '{\\small\\itshape', '}')
+  end
+end
+
+return {
+  { Meta = function(meta) if meta.columns then columns = pandoc.utils.stringify(meta.columns) end end },
+  { Div = div },
+}
+"#;
+
+fn css_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// For HTML based PDF engines. Page margin boxes are only supported by weasyprint.
+pub fn newspaper_css(masthead: &str, dateline: &str, columns: u8) -> String {
+    format!(
+        "@page {{ size: A4; margin: 1.5cm; @top-left {{ content: {}; font-size: 8pt; }} @top-right {{ content: {}; font-size: 8pt; }} @bottom-center {{ content: counter(page); font-size: 8pt; }} }}
+@page :first {{ @top-left {{ content: none; }} @top-right {{ content: none; }} }}
+body {{ max-width: none; margin: 0; padding: 0; font-family: Georgia, serif; font-size: 10pt; text-align: justify; }}
+.masthead {{ text-align: center; border-bottom: 1px solid black; margin-bottom: 1em; }}
+.masthead-title {{ font-size: 40pt; font-weight: bold; margin: 0; }}
+.masthead-dateline {{ border-top: 1px solid black; padding: 0.3em 0; margin: 0.4em 0 0 0; font-size: 9pt; }}
+.byline {{ font-size: 9pt; font-style: italic; }}
+.article-body {{ column-count: {}; column-gap: 1.5em; column-rule: 1px solid #999; }}
+.pull-quote {{ font-size: 13pt; font-style: italic; text-align: center; border-top: 1px solid; border-bottom: 1px solid; margin: 0.8em 0; break-inside: avoid; }}",
+        css_string(masthead),
+        css_string(dateline),
+        columns
+    )
+}
+
+pub fn html_masthead(masthead: &str, dateline: &str) -> String {
+    format!(
+        "<header class=\"masthead\"><p class=\"masthead-title\">{}</p><p class=\"masthead-dateline\">{}</p></header>",
+        escape_text(masthead),
+        escape_text(dateline)
+    )
+}
@@ -0,0 +1,370 @@
+use crate::article::error_response;
+use crate::config::Config;
+use crate::feeds::{is_valid_url, Feed};
+use crate::sanitize::escape_text;
+use axum::{
+    extract::{Extension, Multipart},
+    http::{header, Response, StatusCode},
+    response::{self, IntoResponse, Redirect},
+    body::Body,
+};
+use leptos::logging;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+struct Outline {
+    text: String,
+    xml_url: Option<String>,
+    html_url: Option<String>,
+    description: Option<String>,
+    children: Vec<Outline>,
+}
+
+fn parse_outline(element: &quick_xml::events::BytesStart) -> Outline {
+    let mut outline = Outline::default();
+    let mut title = None;
+
+    for attr in element.attributes().filter_map(|attr| attr.ok()) {
+        let value = match attr.unescape_value() {
+            Ok(value) => value.to_string(),
+            Err(_) => continue,
+        };
+        match attr.key.as_ref() {
+            b"text" => outline.text = value,
+            b"title" => title = Some(value),
+            b"xmlUrl" => outline.xml_url = Some(value),
+            b"htmlUrl" => outline.html_url = Some(value),
+            b"description" => outline.description = Some(value),
+            _ => {}
+        }
+    }
+
+    // `text` is required by the spec, but plenty of exporters only set `title`
+    if outline.text.is_empty() {
+        outline.text = title.unwrap_or_default();
+    }
+    return outline;
+}
+
+fn parse_opml(content: &str) -> Result<Vec<Outline>, String> {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+
+    // The bottom of the stack collects the top level outlines of <body>
+    let mut stack = vec![Outline::default()];
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"outline" => {
+                stack.push(parse_outline(&e));
+            }
+            Ok(Event::Empty(e)) if e.local_name().as_ref() == b"outline" => {
+                let outline = parse_outline(&e);
+                stack.last_mut().unwrap().children.push(outline);
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"outline" => {
+                if stack.len() < 2 {
+                    return Err("Unbalanced outline elements".to_string());
+                }
+                let outline = stack.pop().unwrap();
+                stack.last_mut().unwrap().children.push(outline);
+            }
+            Ok(Event::Eof) => break,
+            Err(err) => return Err(format!("Error parsing OPML: {}", err)),
+            _ => {}
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err("Unbalanced outline elements".to_string());
+    }
+    return Ok(stack.pop().unwrap().children);
+}
+
+async fn get_or_create_category(
+    pool: &SqlitePool,
+    name: &str,
+    parent_id: Option<i64>,
+) -> Result<i64, sqlx::Error> {
+    let existing: Option<(i64,)> = sqlx::query_as("SELECT id FROM categories WHERE name = ? AND parent_id IS ?")
+        .bind(name)
+        .bind(parent_id)
+        .fetch_optional(pool)
+        .await?;
+
+    if let Some((id,)) = existing {
+        return Ok(id);
+    }
+
+    let id = sqlx::query("INSERT INTO categories (name, parent_id) VALUES (?, ?)")
+        .bind(name)
+        .bind(parent_id)
+        .execute(pool)
+        .await?
+        .last_insert_rowid();
+
+    return Ok(id);
+}
+
+async fn import_feed(
+    pool: &SqlitePool,
+    config: &Config,
+    outline: &Outline,
+    url: &str,
+    category_id: Option<i64>,
+) -> Result<bool, sqlx::Error> {
+    let existing: Option<(i64,)> = sqlx::query_as("SELECT id FROM feeds WHERE url = ?")
+        .bind(url)
+        .fetch_optional(pool)
+        .await?;
+
+    let (feed_id, inserted) = match existing {
+        Some((id,)) => (id, false),
+        None => {
+            let title = if outline.text.is_empty() { url } else { outline.text.as_str() };
+            let id = sqlx::query("INSERT INTO feeds (url, title, description, refresh_interval) VALUES (?, ?, ?, ?)")
+                .bind(url)
+                .bind(title)
+                .bind(outline.description.clone().unwrap_or_default())
+                .bind(config.refresh.default_interval_secs)
+                .execute(pool)
+                .await?
+                .last_insert_rowid();
+            (id, true)
+        }
+    };
+
+    if let Some(category_id) = category_id {
+        sqlx::query("INSERT OR IGNORE INTO feed_categories (feed_id, category_id) VALUES (?, ?)")
+            .bind(feed_id)
+            .bind(category_id)
+            .execute(pool)
+            .await?;
+    }
+
+    return Ok(inserted);
+}
+
+// Imports outlines depth first and in document order, turning outlines without a feed URL
+// into categories. Returns the number of newly added feeds and of those that were skipped,
+// because their URL is invalid or they only link to a website.
+async fn import_outlines(
+    pool: &SqlitePool,
+    config: &Config,
+    outlines: Vec<Outline>,
+) -> Result<(usize, usize), sqlx::Error> {
+    let mut imported = 0;
+    let mut failed = 0;
+    // Children are pushed in reverse, so the first one is popped first
+    let mut stack: Vec<(Outline, Option<i64>)> = outlines
+        .into_iter()
+        .rev()
+        .map(|outline| (outline, None))
+        .collect();
+
+    while let Some((mut outline, category_id)) = stack.pop() {
+        match outline.xml_url.clone() {
+            Some(url) if is_valid_url(url.clone()) => {
+                if import_feed(pool, config, &outline, &url, category_id).await? {
+                    imported += 1;
+                }
+            }
+            Some(url) => {
+                logging::warn!("Skipping OPML outline with invalid feed URL {}", url);
+                failed += 1;
+            }
+            None if outline.html_url.is_some() && outline.children.is_empty() => {
+                logging::warn!("Skipping OPML outline {} without a feed URL", outline.text);
+                failed += 1;
+            }
+            None => {
+                let id = get_or_create_category(pool, &outline.text, category_id).await?;
+                let children = std::mem::take(&mut outline.children);
+                stack.extend(children.into_iter().rev().map(|child| (child, Some(id))));
+            }
+        }
+    }
+
+    return Ok((imported, failed));
+}
+
+fn write_feed_outline(opml: &mut String, feed: &Feed, depth: usize) {
+    opml.push_str(&format!(
+        "{}<outline type=\"rss\" text=\"{}\" title=\"{}\" description=\"{}\" xmlUrl=\"{}\"/>\n",
+        "  ".repeat(depth),
+        escape_text(&feed.display_title()),
+        escape_text(&feed.title),
+        escape_text(&feed.description),
+        escape_text(&feed.url),
+    ));
+}
+
+struct ExportTree {
+    categories: Vec<(i64, String, Option<i64>)>,
+    feeds: HashMap<i64, Feed>,
+    memberships: Vec<(i64, i64)>,
+}
+
+impl ExportTree {
+    fn write_category(&self, opml: &mut String, id: i64, name: &str, depth: usize) {
+        let indent = "  ".repeat(depth);
+        opml.push_str(&format!("{}<outline text=\"{}\">\n", indent, escape_text(name)));
+
+        for (child_id, child_name, _) in self.categories.iter().filter(|(_, _, parent)| *parent == Some(id)) {
+            self.write_category(opml, *child_id, child_name, depth + 1);
+        }
+        for (feed_id, _) in self.memberships.iter().filter(|(_, category)| *category == id) {
+            if let Some(feed) = self.feeds.get(feed_id) {
+                write_feed_outline(opml, feed, depth + 1);
+            }
+        }
+
+        opml.push_str(&format!("{}</outline>\n", indent));
+    }
+}
+
+async fn export_opml(pool: &SqlitePool) -> Result<String, sqlx::Error> {
+    let feeds = sqlx::query_as::<_, Feed>("SELECT * FROM feeds ORDER BY title")
+        .fetch_all(pool)
+        .await?;
+    let categories: Vec<(i64, String, Option<i64>)> =
+        sqlx::query_as("SELECT id, name, parent_id FROM categories ORDER BY name")
+            .fetch_all(pool)
+            .await?;
+    let memberships: Vec<(i64, i64)> = sqlx::query_as("SELECT feed_id, category_id FROM feed_categories")
+        .fetch_all(pool)
+        .await?;
+
+    let mut opml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n");
+    opml.push_str("  <head>\n    <title>RSS Newspaper Generator feeds</title>\n");
+    opml.push_str(&format!("    <dateCreated>{}</dateCreated>\n", chrono::Utc::now().to_rfc2822()));
+    opml.push_str("  </head>\n  <body>\n");
+
+    let uncategorized = feeds
+        .iter()
+        .filter(|feed| !memberships.iter().any(|(feed_id, _)| *feed_id == feed.id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let tree = ExportTree {
+        categories,
+        feeds: feeds.into_iter().map(|feed| (feed.id, feed)).collect(),
+        memberships,
+    };
+
+    for (id, name, _) in tree.categories.iter().filter(|(_, _, parent)| parent.is_none()) {
+        tree.write_category(&mut opml, *id, name, 2);
+    }
+    for feed in &uncategorized {
+        write_feed_outline(&mut opml, feed, 2);
+    }
+
+    opml.push_str("  </body>\n</opml>\n");
+    return Ok(opml);
+}
+
+pub async fn get_opml_export(Extension(pool): Extension<SqlitePool>) -> response::Response {
+    let opml = match export_opml(&pool).await {
+        Ok(opml) => opml,
+        Err(e) => return error_response(format!("Error exporting feeds: {}", e)),
+    };
+
+    return Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/x-opml")
+        .header(header::CONTENT_DISPOSITION, "attachment; filename=\"feeds.opml\"")
+        .body(Body::from(opml))
+        .unwrap();
+}
+
+pub async fn post_opml_import(
+    Extension(config): Extension<Config>,
+    Extension(pool): Extension<SqlitePool>,
+    mut multipart: Multipart,
+) -> response::Response {
+    let mut content = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("file") => match field.text().await {
+                Ok(text) => content = Some(text),
+                Err(e) => return error_response(format!("Error reading upload: {}", e)),
+            },
+            Ok(Some(_)) => continue,
+            Ok(None) => break,
+            Err(e) => return error_response(format!("Error reading upload: {}", e)),
+        }
+    }
+
+    let outlines = match content.as_deref().map(parse_opml) {
+        Some(Ok(outlines)) => outlines,
+        Some(Err(e)) => return error_response(e),
+        None => return error_response("No OPML file uploaded".to_string()),
+    };
+
+    match import_outlines(&pool, &config, outlines).await {
+        Ok((imported, failed)) => logging::log!("Imported {} feeds from OPML, skipped {}", imported, failed),
+        Err(e) => return error_response(format!("Error importing feeds: {}", e)),
+    }
+
+    return Redirect::to("/feeds").into_response();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_categories() {
+        let opml = r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <body>
+    <outline text="News">
+      <outline text="World">
+        <outline text="Example" xmlUrl="https://example.com/feed.xml"/>
+      </outline>
+      <outline text="Other" xmlUrl="https://example.org/rss"/>
+    </outline>
+    <outline text="Top" xmlUrl="https://example.net/atom.xml"/>
+  </body>
+</opml>"#;
+
+        let outlines = parse_opml(opml).unwrap();
+        assert_eq!(outlines.len(), 2);
+        assert_eq!(outlines[0].text, "News");
+        assert_eq!(outlines[0].xml_url, None);
+        assert_eq!(outlines[0].children.len(), 2);
+        assert_eq!(outlines[0].children[0].text, "World");
+        assert_eq!(outlines[0].children[0].children[0].xml_url.as_deref(), Some("https://example.com/feed.xml"));
+        assert_eq!(outlines[0].children[1].text, "Other");
+        assert_eq!(outlines[1].xml_url.as_deref(), Some("https://example.net/atom.xml"));
+    }
+
+    #[test]
+    fn falls_back_to_title() {
+        let opml = r#"<opml><body><outline title="Example &amp; Co" xmlUrl="https://example.com/feed"/></body></opml>"#;
+
+        let outlines = parse_opml(opml).unwrap();
+        assert_eq!(outlines[0].text, "Example & Co");
+    }
+
+    #[test]
+    fn keeps_website_links_apart_from_feeds() {
+        let opml = r#"<opml><body><outline text="Example" htmlUrl="https://example.com/"/></body></opml>"#;
+
+        let outlines = parse_opml(opml).unwrap();
+        assert_eq!(outlines[0].xml_url, None);
+        assert_eq!(outlines[0].html_url.as_deref(), Some("https://example.com/"));
+        assert!(outlines[0].children.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        assert!(parse_opml("<opml><body><outline text=\"News\"></body></opml>").is_err());
+        assert!(parse_opml("<opml><body></outline></body></opml>").is_err());
+        assert!(parse_opml("<opml><body><outline text=\"News\">").is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::layout::Layout;
+use crate::breadcrumbs::{BreadCrumbItem, BreadCrumbs};
+use crate::errors::FeedError;
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+// Ways of dealing with the images of an article. E-ink screens get grayscale images,
+// optionally dithered to the 16 shades they can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(rename_all = "lowercase"))]
+pub enum ImageMode {
+    Keep,
+    Grayscale,
+    Dither,
+    Remove,
+}
+
+impl ImageMode {
+    pub const ALL: [ImageMode; 4] = [ImageMode::Keep, ImageMode::Grayscale, ImageMode::Dither, ImageMode::Remove];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageMode::Keep => "keep",
+            ImageMode::Grayscale => "grayscale",
+            ImageMode::Dither => "dither",
+            ImageMode::Remove => "remove",
+        }
+    }
+}
+
+// Page geometry of a printer or e-reader
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct Profile {
+    pub id: i64,
+    pub name: String,
+    pub page_width_mm: f64,
+    pub page_height_mm: f64,
+    pub margin_mm: f64,
+    pub font_size_pt: f64,
+    pub columns: i64,
+    pub images: ImageMode,
+    pub builtin: bool,
+    // Resolution images are scaled to
+    pub dpi: f64,
+}
+
+impl Profile {
+    pub fn keep_images(&self) -> bool {
+        return self.images != ImageMode::Remove;
+    }
+
+    pub fn grayscale(&self) -> bool {
+        return matches!(self.images, ImageMode::Grayscale | ImageMode::Dither);
+    }
+
+    pub fn dither(&self) -> bool {
+        return self.images == ImageMode::Dither;
+    }
+}
+
+// The profile named in a download, or none for the renderer's defaults
+#[cfg(feature = "ssr")]
+pub async fn find_profile(pool: &sqlx::SqlitePool, name: Option<&str>) -> Result<Option<Profile>, String> {
+    let Some(name) = name.filter(|name| !name.is_empty()) else {
+        return Ok(None);
+    };
+
+    let profile = sqlx::query_as::<_, Profile>("SELECT * FROM profiles WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Error loading profile {}: {}", name, e))?;
+
+    match profile {
+        Some(profile) => Ok(Some(profile)),
+        None => Err(format!("Unknown profile: {}", name)),
+    }
+}
+
+#[server]
+pub async fn get_profiles() -> Result<Vec<Profile>, ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    let profiles = sqlx::query_as::<_, Profile>("SELECT * FROM profiles ORDER BY builtin DESC, name")
+        .fetch_all(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    return Ok(profiles);
+}
+
+#[server]
+pub async fn add_profile(
+    name: String,
+    page_width_mm: f64,
+    page_height_mm: f64,
+    margin_mm: f64,
+    font_size_pt: f64,
+    columns: i64,
+    images: ImageMode,
+    dpi: f64,
+) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let invalid = |message: &str| ServerFnError::from(FeedError::InvalidInput(message.to_string()));
+
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(invalid("Profile name must not be empty"));
+    }
+    // Names end up in download URLs and on the command line
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(invalid("Profile names may only contain letters, digits, - and _"));
+    }
+    if page_width_mm <= 0.0 || page_height_mm <= 0.0 || font_size_pt <= 0.0 {
+        return Err(invalid("Page size and font size must be positive"));
+    }
+    if margin_mm < 0.0 || 2.0 * margin_mm >= page_width_mm.min(page_height_mm) {
+        return Err(invalid("Margins don't leave room for the text"));
+    }
+    if !(1..=3).contains(&columns) {
+        return Err(invalid("Profiles have one to three columns"));
+    }
+    if !(72.0..=600.0).contains(&dpi) {
+        return Err(invalid("Resolution must be between 72 and 600 dpi"));
+    }
+
+    let pool = use_pool()?;
+    sqlx::query(
+        "INSERT INTO profiles (name, page_width_mm, page_height_mm, margin_mm, font_size_pt, columns, images, dpi)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(name)
+    .bind(page_width_mm)
+    .bind(page_height_mm)
+    .bind(margin_mm)
+    .bind(font_size_pt)
+    .bind(columns)
+    .bind(images)
+    .bind(dpi)
+    .execute(&pool)
+    .await
+    .map_err(FeedError::from)?;
+
+    return Ok(());
+}
+
+#[server]
+pub async fn delete_profile(id: i64) -> Result<(), ServerFnError<FeedError>> {
+    use crate::db::use_pool;
+
+    let pool = use_pool()?;
+    let result = sqlx::query("DELETE FROM profiles WHERE id = ? AND builtin = 0")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(FeedError::from)?;
+
+    // Built-in profiles are left alone like unknown ones
+    if result.rows_affected() == 0 {
+        return Err(FeedError::NotFound(format!("custom profile {}", id)).into());
+    }
+
+    return Ok(());
+}
+
+// Options for a profile <select>, the first one uses the renderer's defaults
+#[component]
+pub fn ProfileOptions(profiles: Vec<Profile>) -> impl IntoView {
+    view! {
+        <option value="">Default</option>
+        {profiles
+            .into_iter()
+            .map(|profile| view! { <option value=profile.name.clone()>{profile.name}</option> })
+            .collect_view()}
+    }
+}
+
+#[component]
+pub fn ProfilesView() -> impl IntoView {
+    let add_profile = create_server_action::<AddProfile>();
+    let delete_profile = create_server_action::<DeleteProfile>();
+
+    let profiles = create_resource(
+        move || (add_profile.version().get(), delete_profile.version().get()),
+        |_| async move { get_profiles().await.unwrap_or_default() },
+    );
+
+    view! {
+        <Layout headline="Profiles".to_string()>
+            <BreadCrumbs items=vec![
+                BreadCrumbItem { text: "Feeds".to_string(), url: "/feeds".to_string() },
+                BreadCrumbItem { text: "Profiles".to_string(), url: "/profiles".to_string() },
+            ] />
+            <div class="max-w-[700px]">
+                <Suspense fallback=|| view! { <p>Loading...</p> }>
+                    {move || profiles.get().map(|profiles| view! {
+                        <table class="w-full my-4 text-sm">
+                            <tr class="text-left">
+                                <th>Name</th>
+                                <th>Page</th>
+                                <th>Margin</th>
+                                <th>Font</th>
+                                <th>Columns</th>
+                                <th>Images</th>
+                                <th>Resolution</th>
+                                <th></th>
+                            </tr>
+                            {profiles
+                                .into_iter()
+                                .map(|profile| {
+                                    let id = profile.id;
+                                    let builtin = profile.builtin;
+                                    view! {
+                                        <tr>
+                                            <td>{profile.name}</td>
+                                            <td>{format!("{} × {} mm", profile.page_width_mm, profile.page_height_mm)}</td>
+                                            <td>{format!("{} mm", profile.margin_mm)}</td>
+                                            <td>{format!("{} pt", profile.font_size_pt)}</td>
+                                            <td>{profile.columns}</td>
+                                            <td>{profile.images.as_str()}</td>
+                                            <td>{format!("{} dpi", profile.dpi)}</td>
+                                            <td>
+                                                <Show when=move || !builtin>
+                                                    <button
+                                                        class="p-1 rounded bg-slate-100"
+                                                        on:click=move |_| delete_profile.dispatch(DeleteProfile { id })
+                                                    >
+                                                        Delete
+                                                    </button>
+                                                </Show>
+                                            </td>
+                                        </tr>
+                                    }
+                                })
+                                .collect_view()}
+                        </table>
+                    })}
+                </Suspense>
+                <ActionForm action=add_profile class="flex flex-col gap-2 my-4">
+                    <label class="flex flex-col text-sm">
+                        "Name"
+                        <input class="p-2 rounded border" type="text" name="name" required />
+                    </label>
+                    <div class="flex gap-2">
+                        <label class="flex flex-col text-sm flex-1">
+                            "Page width (mm)"
+                            <input class="p-2 rounded border" type="number" step="0.1" min="1" name="page_width_mm" required />
+                        </label>
+                        <label class="flex flex-col text-sm flex-1">
+                            "Page height (mm)"
+                            <input class="p-2 rounded border" type="number" step="0.1" min="1" name="page_height_mm" required />
+                        </label>
+                        <label class="flex flex-col text-sm flex-1">
+                            "Margin (mm)"
+                            <input class="p-2 rounded border" type="number" step="0.1" min="0" name="margin_mm" value="10" />
+                        </label>
+                    </div>
+                    <div class="flex gap-2">
+                        <label class="flex flex-col text-sm flex-1">
+                            "Font size (pt)"
+                            <input class="p-2 rounded border" type="number" step="0.5" min="1" name="font_size_pt" value="11" />
+                        </label>
+                        <label class="flex flex-col text-sm flex-1">
+                            "Columns"
+                            <input class="p-2 rounded border" type="number" min="1" max="3" name="columns" value="1" />
+                        </label>
+                        <label class="flex flex-col text-sm flex-1">
+                            "Images"
+                            <select class="p-2 rounded border" name="images">
+                                {ImageMode::ALL
+                                    .map(|mode| view! { <option value=mode.as_str()>{mode.as_str()}</option> })
+                                    .collect_view()}
+                            </select>
+                        </label>
+                        <label class="flex flex-col text-sm flex-1">
+                            "Resolution (dpi)"
+                            <input class="p-2 rounded border" type="number" min="72" max="600" name="dpi" value="150" />
+                        </label>
+                    </div>
+                    <div class="flex items-center gap-2">
+                        <button class="p-2 rounded bg-slate-100" type="submit">Add Profile</button>
+                        {move || match add_profile.value().get() {
+                            Some(Err(err)) => view! { <span class="text-red-600">{err.to_string()}</span> }.into_view(),
+                            _ => ().into_view(),
+                        }}
+                    </div>
+                </ActionForm>
+            </div>
+        </Layout>
+    }
+}
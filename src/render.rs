@@ -0,0 +1,649 @@
+use crate::config::{Config, PdfRenderer};
+use crate::newspaper::{self, PageLayout};
+use crate::profiles::{find_profile, Profile};
+use scraper::ElementRef;
+use std::path::PathBuf;
+
+// A formatted article or edition, ready to be rendered
+#[derive(Clone, Debug, Default)]
+pub struct Document {
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    pub publisher: Option<String>,
+    pub source: Option<String>,
+    // Sanitized HTML of the body
+    pub html: String,
+    // Editions open with a title page and a table of contents. Their feeds are <h1>
+    // and their articles <h2>.
+    pub table_of_contents: bool,
+    pub layout: PageLayout,
+    // Edition number and build date, shown in the masthead of the newspaper layout
+    pub number: Option<i64>,
+    pub issued: Option<chrono::DateTime<chrono::Local>>,
+    // Page size, margins and font size of PDFs, the renderer's defaults when None
+    pub profile: Option<Profile>,
+}
+
+impl Document {
+    // The newspaper layout replaces the title page with the masthead
+    fn title_page(&self) -> bool {
+        return self.table_of_contents && self.layout == PageLayout::Standard;
+    }
+
+    fn masthead(&self) -> String {
+        return self.publisher.clone().unwrap_or(newspaper::MASTHEAD.to_string());
+    }
+
+    // Documents without a build date are dated when they are rendered
+    fn dateline(&self) -> String {
+        return newspaper::dateline(&self.issued.unwrap_or_else(chrono::Local::now), self.number);
+    }
+
+    // The HTML to render, without images if the profile drops them
+    fn body(&self) -> String {
+        match &self.profile {
+            Some(profile) if !profile.keep_images() => crate::sanitize::strip_images(&self.html),
+            _ => self.html.clone(),
+        }
+    }
+
+    // Extra columns of the standard layout, the newspaper layout sets only article bodies in columns
+    fn page_columns(&self) -> Option<i64> {
+        let profile = self.profile.as_ref().filter(|profile| profile.columns > 1)?;
+        return (self.layout == PageLayout::Standard).then_some(profile.columns);
+    }
+}
+
+// The layout and profile of a PDF download
+pub struct PdfOptions {
+    pub layout: PageLayout,
+    pub profile: Option<Profile>,
+}
+
+impl PdfOptions {
+    // Columns given for the newspaper layout take precedence over those of the profile
+    pub async fn load(
+        pool: &sqlx::SqlitePool,
+        layout: Option<&str>,
+        columns: Option<u8>,
+        profile: Option<&str>,
+    ) -> Result<PdfOptions, String> {
+        let profile = find_profile(pool, profile).await?;
+        let profile_columns = profile
+            .as_ref()
+            .filter(|profile| profile.columns > 1)
+            .map(|profile| profile.columns as u8);
+        let layout = PageLayout::parse(layout, columns.or(profile_columns))?;
+
+        return Ok(PdfOptions { layout, profile });
+    }
+}
+
+pub struct Rendered {
+    pub bytes: Vec<u8>,
+    pub content_type: &'static str,
+}
+
+pub trait Renderer: Send + Sync {
+    fn render(&self, document: &Document) -> Result<Rendered, String>;
+}
+
+// Temporary directory for the files of a single render, removed when dropped
+struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    fn new() -> Result<ScratchDir, String> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        static RENDERS: AtomicU64 = AtomicU64::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "rss-newspaper-{}-{}",
+            std::process::id(),
+            RENDERS.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).map_err(|e| format!("Error creating {}: {}", path.display(), e))?;
+
+        return Ok(ScratchDir { path });
+    }
+
+    fn write(&self, name: &str, content: impl AsRef<[u8]>) -> Result<PathBuf, String> {
+        let path = self.path.join(name);
+        std::fs::write(&path, content).map_err(|e| format!("Error writing {}: {}", path.display(), e))?;
+        return Ok(path);
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+// PDFs are rendered by the backend chosen in the configuration
+pub fn pdf_renderer(config: &Config) -> Box<dyn Renderer> {
+    match config.renderer {
+        PdfRenderer::Pandoc => Box::new(PandocPdf { pdf_engine: PathBuf::from(&config.pdf_engine) }),
+        PdfRenderer::Typst => Box::new(TypstPdf { typst_path: config.typst_path.clone() }),
+    }
+}
+
+pub fn epub_renderer() -> Box<dyn Renderer> {
+    return Box::new(PandocEpub);
+}
+
+// Pandoc and typst take seconds, so they run on a blocking thread instead of a runtime worker
+pub async fn render_blocking(renderer: Box<dyn Renderer>, document: Document) -> Result<Rendered, String> {
+    return tokio::task::spawn_blocking(move || renderer.render(&document))
+        .await
+        .map_err(|e| format!("Error rendering document: {}", e))?;
+}
+
+fn pandoc_metadata(document: &Document) -> Vec<pandoc::PandocOption> {
+    use pandoc::PandocOption;
+
+    let fields = [
+        ("title", &document.title),
+        ("subtitle", &document.subtitle),
+        ("author", &document.author),
+        ("date", &document.date),
+        ("publisher", &document.publisher),
+        ("source", &document.source),
+    ];
+
+    let mut options = fields
+        .into_iter()
+        .filter_map(|(name, value)| value.clone().map(|value| PandocOption::Meta(name.to_string(), Some(value))))
+        .collect::<Vec<_>>();
+    if document.table_of_contents {
+        options.push(PandocOption::TableOfContents);
+    }
+    return options;
+}
+
+fn run_pandoc(
+    html: String,
+    output_format: pandoc::OutputFormat,
+    options: Vec<pandoc::PandocOption>,
+) -> Result<Vec<u8>, String> {
+    use pandoc::{Pandoc, InputKind, InputFormat, OutputKind};
+
+    let mut pandoc = Pandoc::new();
+
+    pandoc.set_input_format(InputFormat::Html, Vec::new());
+    pandoc.set_output_format(output_format, Vec::new());
+
+    pandoc.set_input(InputKind::Pipe(html));
+    pandoc.set_output(OutputKind::Pipe);
+
+    pandoc.add_options(&options);
+
+    // Execute pandoc
+    match pandoc.execute() {
+        Ok(pandoc::PandocOutput::ToBuffer(buffer)) => Ok(buffer.into()),
+        Ok(pandoc::PandocOutput::ToBufferRaw(buffer)) => Ok(buffer),
+        Ok(pandoc::PandocOutput::ToFile(_)) => Err("Pandoc output to file not supported".to_string()),
+        Err(e) => Err(format!("Error converting article: {}", e)),
+    }
+}
+
+// Pandoc with a LaTeX engine, or an HTML based one such as wkhtmltopdf or weasyprint
+pub struct PandocPdf {
+    pdf_engine: PathBuf,
+}
+
+impl PandocPdf {
+    fn html_engine(&self) -> bool {
+        let name = self.pdf_engine.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        return matches!(name, "wkhtmltopdf" | "weasyprint" | "prince" | "pagedjs-cli");
+    }
+
+    // The masthead is included before the body, which pandoc would otherwise open with the
+    // title block. Article bodies, pull quotes and bylines are divs the filter or CSS lays out.
+    fn newspaper_options(&self, document: &Document, scratch: &ScratchDir, columns: u8) -> Result<Vec<pandoc::PandocOption>, String> {
+        use pandoc::PandocOption;
+
+        let masthead = document.masthead();
+        let dateline = document.dateline();
+
+        let mut options = vec![
+            PandocOption::Meta("pagetitle".to_string(), Some(masthead.clone())),
+            PandocOption::Meta("columns".to_string(), Some(columns.to_string())),
+            PandocOption::LuaFilter(scratch.write("newspaper.lua", newspaper::NEWSPAPER_FILTER)?),
+        ];
+        if self.html_engine() {
+            let css = scratch.write("newspaper.css", &newspaper::newspaper_css(&masthead, &dateline, columns))?;
+            options.push(PandocOption::Css(css.display().to_string()));
+            options.push(PandocOption::Var("include-before".to_string(), Some(newspaper::html_masthead(&masthead, &dateline))));
+        } else {
+            if document.profile.is_none() {
+                options.push(PandocOption::Var("geometry".to_string(), Some("margin=1.5cm".to_string())));
+            }
+            options.push(PandocOption::Var("header-includes".to_string(), Some(newspaper::latex_preamble(&masthead, &dateline))));
+            options.push(PandocOption::Var("include-before".to_string(), Some(newspaper::latex_masthead(&masthead, &dateline))));
+        }
+
+        return Ok(options);
+    }
+
+    // Page size and fonts go into a style sheet for HTML engines and into template variables for LaTeX
+    fn profile_options(&self, document: &Document, profile: &Profile, scratch: &ScratchDir) -> Result<Vec<pandoc::PandocOption>, String> {
+        use pandoc::PandocOption;
+
+        if self.html_engine() {
+            let mut css = format!(
+                "@page {{ size: {}mm {}mm; margin: {}mm; }}\nbody {{ max-width: none; font-size: {}pt; }}\nimg {{ max-width: 100%; }}\n",
+                profile.page_width_mm, profile.page_height_mm, profile.margin_mm, profile.font_size_pt
+            );
+            if let Some(columns) = document.page_columns() {
+                css.push_str(&format!("body {{ column-count: {}; column-gap: 1.5em; }}\n", columns));
+            }
+            let css = scratch.write("profile.css", &css)?;
+            return Ok(vec![PandocOption::Css(css.display().to_string())]);
+        }
+
+        let mut options = vec![
+            PandocOption::Var(
+                "geometry".to_string(),
+                Some(format!(
+                    "paperwidth={}mm,paperheight={}mm,margin={}mm",
+                    profile.page_width_mm, profile.page_height_mm, profile.margin_mm
+                )),
+            ),
+            PandocOption::Var("fontsize".to_string(), Some(format!("{}pt", profile.font_size_pt))),
+        ];
+        // The article class only comes in 10, 11 and 12pt
+        if ![10.0, 11.0, 12.0].contains(&profile.font_size_pt) {
+            options.push(PandocOption::Var("documentclass".to_string(), Some("scrartcl".to_string())));
+        }
+        // The twocolumn class option stops at two, multicol takes any number
+        if let Some(columns) = document.page_columns() {
+            options.push(PandocOption::Var("header-includes".to_string(), Some("\\usepackage{multicol}".to_string())));
+            options.push(PandocOption::Var(
+                "include-before".to_string(),
+                Some(format!("\\begin{{multicols}}{{{}}}", columns)),
+            ));
+            options.push(PandocOption::Var("include-after".to_string(), Some("\\end{multicols}".to_string())));
+        }
+        return Ok(options);
+    }
+}
+
+impl Renderer for PandocPdf {
+    fn render(&self, document: &Document) -> Result<Rendered, String> {
+        use pandoc::{OutputFormat, PandocOption};
+
+        let scratch = ScratchDir::new()?;
+
+        let mut options = match document.layout {
+            PageLayout::Standard => pandoc_metadata(document),
+            PageLayout::Newspaper { columns } => self.newspaper_options(document, &scratch, columns)?,
+        };
+        if let Some(profile) = &document.profile {
+            options.extend(self.profile_options(document, profile, &scratch)?);
+        }
+        options.push(PandocOption::PdfEngine(self.pdf_engine.clone()));
+        if document.title_page() {
+            // The title block becomes the cover page, followed by the table of contents
+            options.push(PandocOption::Var("classoption".to_string(), Some("titlepage".to_string())));
+        }
+
+        let bytes = run_pandoc(document.body(), OutputFormat::Pdf, options)?;
+        return Ok(Rendered { bytes, content_type: "application/pdf" });
+    }
+}
+
+// Pandoc embeds referenced images and generates the nav document
+pub struct PandocEpub;
+
+impl Renderer for PandocEpub {
+    fn render(&self, document: &Document) -> Result<Rendered, String> {
+        use pandoc::{OutputFormat, PandocOption};
+
+        let mut options = pandoc_metadata(document);
+        if document.table_of_contents {
+            // Every article of an edition becomes its own chapter
+            options.push(PandocOption::EpubChapterLevel(2));
+        }
+
+        let bytes = run_pandoc(document.html.clone(), OutputFormat::Epub3, options)?;
+        return Ok(Rendered { bytes, content_type: "application/epub+zip" });
+    }
+}
+
+// Converts the HTML to Typst markup and compiles it with the typst binary,
+// which is a single small download compared to a TeX distribution
+pub struct TypstPdf {
+    typst_path: PathBuf,
+}
+
+impl Renderer for TypstPdf {
+    fn render(&self, document: &Document) -> Result<Rendered, String> {
+        use std::process::Command;
+
+        let scratch = ScratchDir::new()?;
+        let (source, images) = typst_source(document);
+        for (name, bytes) in images {
+            scratch.write(&name, bytes)?;
+        }
+        let input = scratch.write("document.typ", source)?;
+        let output = input.with_extension("pdf");
+
+        let result = Command::new(&self.typst_path)
+            .arg("compile")
+            .arg(&input)
+            .arg(&output)
+            .output()
+            .map_err(|e| format!("Error running {}: {}", self.typst_path.display(), e))?;
+        if !result.status.success() {
+            return Err(format!("Error converting article: {}", String::from_utf8_lossy(&result.stderr)));
+        }
+
+        let bytes = std::fs::read(&output).map_err(|e| format!("Error reading {}: {}", output.display(), e))?;
+        return Ok(Rendered { bytes, content_type: "application/pdf" });
+    }
+}
+
+fn typst_string(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    return format!("\"{}\"", escaped);
+}
+
+// Markup characters are escaped, whitespace is collapsed like a browser would. Text can end up
+// at the start of a line, so the markers of headings, lists and numbered lists (`1.`) are too.
+fn typst_text(text: &str) -> String {
+    let mut markup = String::new();
+    let mut space = false;
+    let mut previous = None;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if space {
+            markup.push(' ');
+            space = false;
+        }
+        if "\\#*_`$<>@[]=-+/~".contains(c) || (c == '.' && previous.is_some_and(|p: char| p.is_ascii_digit())) {
+            markup.push('\\');
+        }
+        markup.push(c);
+        previous = Some(c);
+    }
+    if space {
+        markup.push(' ');
+    }
+    return markup;
+}
+
+// State of an HTML to Typst conversion
+struct TypstConversion {
+    layout: PageLayout,
+    // Resolution the images were scaled to
+    dpi: f64,
+    // Embedded images, written next to the source by the renderer
+    images: Vec<(String, Vec<u8>)>,
+    // A heading ends at the end of its line
+    in_heading: bool,
+}
+
+fn typst_children(element: ElementRef, conversion: &mut TypstConversion, markup: &mut String) {
+    for child in element.children() {
+        if let Some(text) = child.value().as_text() {
+            markup.push_str(&typst_text(text));
+        } else if let Some(child) = ElementRef::wrap(child) {
+            typst_element(child, conversion, markup);
+        }
+    }
+}
+
+fn typst_content(element: ElementRef, conversion: &mut TypstConversion) -> String {
+    let mut markup = String::new();
+    typst_children(element, conversion, &mut markup);
+    return markup;
+}
+
+// Images were scaled to fit a column at the resolution of the profile by embed_images
+fn typst_image(element: ElementRef, conversion: &mut TypstConversion, markup: &mut String) {
+    use crate::images::EmbeddedImage;
+
+    let Some(image) = element.value().attr("src").and_then(EmbeddedImage::from_data_uri) else {
+        // Images that weren't embedded can't be loaded by typst, the description stands in for them
+        if let Some(alt) = element.value().attr("alt").filter(|alt| !alt.trim().is_empty()) {
+            markup.push_str(&format!("#emph[{}];", typst_text(alt)));
+        }
+        return;
+    };
+
+    let name = format!("image-{}.{}", conversion.images.len(), image.extension);
+    markup.push_str(&format!(
+        "#box(image({}, width: {:.2}in));",
+        typst_string(&name),
+        image.width as f64 / conversion.dpi
+    ));
+    conversion.images.push((name, image.bytes));
+}
+
+// The divs of newspaper::format_article, in the newspaper layout
+fn typst_newspaper_div(element: ElementRef, columns: u8, conversion: &mut TypstConversion, markup: &mut String) -> bool {
+    let class = element.value().attr("class").unwrap_or_default();
+    match class {
+        "article-body" => markup.push_str(&format!(
+            "\n\n#columns({}, gutter: 1.5em)[{}];\n\n",
+            columns,
+            typst_content(element, conversion)
+        )),
+        "pull-quote" => markup.push_str(&format!(
+            "\n\n#block(width: 100%, inset: (y: 0.6em), stroke: (y: 0.5pt))[#align(center)[#text(size: 1.3em, style: \"italic\")[{}]]];\n\n",
+            typst_content(element, conversion).trim()
+        )),
+        "byline" => markup.push_str(&format!(
+            "\n\n#text(size: 0.85em, style: \"italic\")[{}];\n\n",
+            typst_content(element, conversion).trim()
+        )),
+        _ => return false,
+    }
+    return true;
+}
+
+// Embedded expressions end with a semicolon, so text right after them isn't parsed as code
+fn typst_element(element: ElementRef, conversion: &mut TypstConversion, markup: &mut String) {
+    let name = element.value().name();
+    if let (PageLayout::Newspaper { columns }, "div") = (conversion.layout, name) {
+        if typst_newspaper_div(element, columns, conversion, markup) {
+            return;
+        }
+    }
+    match name {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse::<usize>().unwrap_or(1);
+            conversion.in_heading = true;
+            let title = typst_content(element, conversion);
+            conversion.in_heading = false;
+            markup.push_str(&format!("\n\n{} {}\n\n", "=".repeat(level), title.trim()));
+        }
+        "p" | "div" | "section" | "article" | "header" | "footer" | "figure" | "table" | "tr" => {
+            markup.push_str("\n\n");
+            typst_children(element, conversion, markup);
+            markup.push_str("\n\n");
+        }
+        "br" if conversion.in_heading => markup.push_str("\\ "),
+        "br" => markup.push_str(" \\\n"),
+        "hr" => markup.push_str("\n\n#line(length: 100%);\n\n"),
+        "strong" | "b" => markup.push_str(&format!("#strong[{}];", typst_content(element, conversion))),
+        "em" | "i" | "cite" | "figcaption" => markup.push_str(&format!("#emph[{}];", typst_content(element, conversion))),
+        "sub" => markup.push_str(&format!("#sub[{}];", typst_content(element, conversion))),
+        "sup" => markup.push_str(&format!("#super[{}];", typst_content(element, conversion))),
+        "s" | "del" | "strike" => markup.push_str(&format!("#strike[{}];", typst_content(element, conversion))),
+        "a" => match element.value().attr("href").filter(|href| href.starts_with("http")) {
+            Some(href) => markup.push_str(&format!("#link({})[{}];", typst_string(href), typst_content(element, conversion))),
+            None => typst_children(element, conversion, markup),
+        },
+        "ul" | "ol" => {
+            let items = element
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|child| child.value().name() == "li")
+                .map(|item| format!("[{}]", typst_content(item, conversion).trim()))
+                .collect::<Vec<_>>();
+            let function = if name == "ol" { "enum" } else { "list" };
+            markup.push_str(&format!("\n\n#{}({});\n\n", function, items.join(", ")));
+        }
+        "blockquote" => markup.push_str(&format!("\n\n#quote(block: true)[{}];\n\n", typst_content(element, conversion))),
+        "pre" => {
+            let code = element.text().collect::<String>();
+            markup.push_str(&format!("\n\n#raw({}, block: true);\n\n", typst_string(&code)));
+        }
+        "code" => {
+            let code = element.text().collect::<String>();
+            markup.push_str(&format!("#raw({});", typst_string(&code)));
+        }
+        "img" => typst_image(element, conversion, markup),
+        "iframe" | "script" | "style" => {}
+        _ => typst_children(element, conversion, markup),
+    }
+}
+
+// Typst markup and the images it refers to
+pub fn html_to_typst(html: &str, layout: PageLayout, dpi: f64) -> (String, Vec<(String, Vec<u8>)>) {
+    let fragment = scraper::Html::parse_fragment(html);
+    let mut conversion = TypstConversion { layout, dpi, images: Vec::new(), in_heading: false };
+    let markup = typst_content(fragment.root_element(), &mut conversion);
+    return (markup, conversion.images);
+}
+
+// Page headers and footers of the newspaper layout
+fn typst_newspaper_page(document: &Document) -> String {
+    let masthead = typst_text(&document.masthead());
+    let dateline = typst_text(&document.dateline());
+
+    return format!(
+        "#set page(paper: \"a4\", margin: 1.5cm,
+  header: context if here().page() > 1 [#set text(size: 8pt); #smallcaps[{masthead}]; #h(1fr) {dateline}],
+  footer: context [#set align(center); #set text(size: 8pt); #counter(page).display();])
+#set text(size: 10pt)
+#set par(justify: true)
+"
+    );
+}
+
+// At the top of the first page of the newspaper layout
+fn typst_masthead(document: &Document) -> String {
+    let masthead = typst_text(&document.masthead());
+    let dateline = typst_text(&document.dateline());
+
+    return format!(
+        "#align(center)[
+  #text(size: 40pt, weight: \"bold\")[{masthead}];
+  #line(length: 100%);
+  #text(size: 9pt)[{dateline}];
+  #line(length: 100%);
+]
+"
+    );
+}
+
+fn typst_source(document: &Document) -> (String, Vec<(String, Vec<u8>)>) {
+    let mut source = String::new();
+
+    match document.layout {
+        PageLayout::Standard => {
+            source.push_str("#set page(paper: \"a4\", margin: 2cm, numbering: \"1\")\n");
+            source.push_str("#set par(justify: true)\n");
+        }
+        PageLayout::Newspaper { .. } => source.push_str(&typst_newspaper_page(document)),
+    }
+    // Set after the layout's page, so the profile overrides its size, margins and font size
+    if let Some(profile) = &document.profile {
+        source.push_str(&format!(
+            "#set page(width: {}mm, height: {}mm, margin: {}mm)\n#set text(size: {}pt)\n",
+            profile.page_width_mm, profile.page_height_mm, profile.margin_mm, profile.font_size_pt
+        ));
+    }
+    if let Some(columns) = document.page_columns() {
+        source.push_str(&format!("#set page(columns: {})\n", columns));
+    }
+    if let Some(title) = &document.title {
+        source.push_str(&format!("#set document(title: {})\n", typst_string(title)));
+    }
+    if let Some(author) = &document.author {
+        source.push_str(&format!("#set document(author: {})\n", typst_string(author)));
+    }
+    if let PageLayout::Newspaper { .. } = document.layout {
+        source.push_str(&typst_masthead(document));
+    }
+
+    if document.title_page() {
+        source.push_str("#align(center + horizon)[\n");
+        if let Some(title) = &document.title {
+            source.push_str(&format!("#text(size: 28pt, weight: \"bold\")[{}]\n\n", typst_text(title)));
+        }
+        if let Some(subtitle) = &document.subtitle {
+            source.push_str(&format!("#text(size: 14pt)[{}]\n\n", typst_text(subtitle)));
+        }
+        if let Some(date) = &document.date {
+            source.push_str(&format!("{}\n", typst_text(date)));
+        }
+        source.push_str("]\n#pagebreak()\n#outline(depth: 2)\n#pagebreak()\n");
+    }
+
+    let dpi = document.profile.as_ref().map(|profile| profile.dpi).unwrap_or(crate::images::DPI);
+    let (body, images) = html_to_typst(&document.body(), document.layout, dpi);
+    source.push_str(&body);
+    return (source, images);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typst(html: &str) -> String {
+        return html_to_typst(html, PageLayout::Standard, 150.0).0;
+    }
+
+    #[test]
+    fn escapes_markup_characters() {
+        assert_eq!(
+            typst_text("#a *b* _c_ `d` $e$ <f> @g [h] ~i \\j"),
+            "\\#a \\*b\\* \\_c\\_ \\`d\\` \\$e\\$ \\<f\\> \\@g \\[h\\] \\~i \\\\j"
+        );
+    }
+
+    #[test]
+    fn escapes_line_start_markers() {
+        assert_eq!(typst_text("= Heading"), "\\= Heading");
+        assert_eq!(typst_text("- item"), "\\- item");
+        assert_eq!(typst_text("+ item"), "\\+ item");
+        assert_eq!(typst_text("/ term: description"), "\\/ term: description");
+        assert_eq!(typst_text("12. item"), "12\\. item");
+    }
+
+    #[test]
+    fn keeps_paragraphs_starting_with_numbers() {
+        let markup = typst("<p>1. First</p><p>2. Second</p>");
+        assert!(markup.contains("1\\. First"));
+        assert!(markup.contains("2\\. Second"));
+        assert!(!markup.lines().any(|line| line.starts_with("1.")));
+    }
+
+    #[test]
+    fn collapses_whitespace() {
+        assert_eq!(typst_text("a \n\t b"), "a b");
+    }
+
+    #[test]
+    fn keeps_line_breaks_of_headings_in_the_heading() {
+        let markup = typst("<h2>One<br>Two</h2><p>Text</p>");
+        assert!(markup.contains("== One\\ Two\n"));
+    }
+
+    #[test]
+    fn breaks_lines_of_paragraphs() {
+        let markup = typst("<p>One<br>Two</p>");
+        assert!(markup.contains("One \\\nTwo"));
+    }
+}
@@ -0,0 +1,60 @@
+use ammonia::Builder;
+
+// Cleans untrusted HTML from feeds and scraped pages. Ammonia's default allow-list
+// already drops scripts, styles, event handler attributes and `javascript:` URLs.
+// Embedded iframes (videos, maps, ...) are only kept when `allow_iframes` is set.
+pub fn sanitize_html(html: &str, allow_iframes: bool) -> String {
+    let mut builder = Builder::default();
+
+    if allow_iframes {
+        builder
+            .add_tags(&["iframe"])
+            .add_tag_attributes("iframe", &["src", "width", "height", "title", "allowfullscreen"]);
+    }
+
+    return builder.clean(html).to_string();
+}
+
+// Item links from feeds end up in href attributes, so only web links are kept.
+// `javascript:` and `data:` links from a hostile feed are dropped.
+pub fn safe_link(link: &str) -> Option<String> {
+    let link = link.trim();
+    match url::Url::parse(link) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Some(link.to_string()),
+        _ => None,
+    }
+}
+
+// For plain text such as titles that gets interpolated into HTML
+pub fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Text content of an HTML fragment, for the search index
+pub fn html_to_text(html: &str) -> String {
+    let fragment = scraper::Html::parse_fragment(html);
+
+    return fragment
+        .root_element()
+        .text()
+        .flat_map(|text| text.split_whitespace())
+        .collect::<Vec<_>>()
+        .join(" ");
+}
+
+// For HTML that went through sanitize_html already, such as a formatted article or edition.
+// Keeps what that lets through, including the classes of the newspaper layout.
+pub fn strip_images(html: &str) -> String {
+    let mut builder = Builder::default();
+
+    builder
+        .rm_tags(&["img"])
+        .add_generic_attributes(&["class"])
+        .add_tags(&["iframe"])
+        .add_tag_attributes("iframe", &["src", "width", "height", "title", "allowfullscreen"]);
+
+    return builder.clean(html).to_string();
+}
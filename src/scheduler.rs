@@ -0,0 +1,138 @@
+use crate::config::Config;
+use crate::feeds::{refresh_feed, Feed};
+use chrono::{DateTime, Duration, Utc};
+use leptos::logging;
+use sqlx::SqlitePool;
+
+// Doubles the refresh interval for every consecutive failure
+fn backoff_secs(feed: &Feed, max_backoff_secs: i64) -> i64 {
+    let factor = 1i64 << feed.failure_count.clamp(0, 16);
+    let backoff = feed.refresh_interval.saturating_mul(factor);
+
+    return backoff.min(max_backoff_secs.max(feed.refresh_interval));
+}
+
+// Dead feeds are only retried by hand
+fn is_due(feed: &Feed, now: DateTime<Utc>, max_backoff_secs: i64) -> bool {
+    if feed.dead {
+        return false;
+    }
+    let last_fetched = feed
+        .last_fetched
+        .as_deref()
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok());
+    let Some(last_fetched) = last_fetched else {
+        return true;
+    };
+
+    // An interval out of chrono's range means the feed isn't due, rather than a panic of the scheduler
+    let next_fetch = Duration::try_seconds(backoff_secs(feed, max_backoff_secs))
+        .and_then(|backoff| last_fetched.with_timezone(&Utc).checked_add_signed(backoff));
+    return next_fetch.is_some_and(|next_fetch| next_fetch <= now);
+}
+
+async fn refresh_due_feeds(pool: &SqlitePool, config: &Config) -> Result<(), sqlx::Error> {
+    let feeds = sqlx::query_as::<_, Feed>("SELECT * FROM feeds")
+        .fetch_all(pool)
+        .await?;
+
+    let now = Utc::now();
+    let max_backoff_secs = config.refresh.max_backoff_secs;
+    for feed in feeds.iter().filter(|feed| is_due(feed, now, max_backoff_secs)) {
+        logging::log!("Refreshing feed: {}", feed.url);
+        if let Err(err) = refresh_feed(pool, config, feed).await {
+            logging::warn!("{} ({})", err, feed.url);
+        }
+    }
+
+    return Ok(());
+}
+
+pub async fn run(pool: SqlitePool, config: Config) {
+    let poll_interval = std::time::Duration::from_secs(config.refresh.poll_interval_secs);
+    let mut interval = tokio::time::interval(poll_interval);
+
+    loop {
+        interval.tick().await;
+        if let Err(err) = refresh_due_feeds(&pool, &config).await {
+            logging::error!("Error loading feeds for refresh: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 60 * 60;
+    const DAY: i64 = 24 * HOUR;
+
+    fn feed(refresh_interval: i64, failure_count: i64, last_fetched: Option<&str>) -> Feed {
+        return Feed {
+            id: 1,
+            url: "https://example.com/feed".to_string(),
+            title: "Example".to_string(),
+            description: String::new(),
+            refresh_interval,
+            last_fetched: last_fetched.map(str::to_string),
+            last_success: None,
+            last_error: None,
+            failure_count,
+            etag: None,
+            last_modified: None,
+            custom_title: None,
+            notes: String::new(),
+            redirect_url: None,
+            redirect_count: 0,
+            dead: false,
+        };
+    }
+
+    fn now() -> DateTime<Utc> {
+        return DateTime::parse_from_rfc3339("2024-08-01T12:00:00Z").unwrap().with_timezone(&Utc);
+    }
+
+    #[test]
+    fn backoff_doubles_per_failure() {
+        assert_eq!(backoff_secs(&feed(HOUR, 0, None), DAY), HOUR);
+        assert_eq!(backoff_secs(&feed(HOUR, 1, None), DAY), 2 * HOUR);
+        assert_eq!(backoff_secs(&feed(HOUR, 3, None), DAY), 8 * HOUR);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff_secs(&feed(HOUR, 10, None), DAY), DAY);
+        // The cap never shortens the feed's own interval
+        assert_eq!(backoff_secs(&feed(2 * DAY, 10, None), DAY), 2 * DAY);
+    }
+
+    #[test]
+    fn backoff_saturates() {
+        assert_eq!(backoff_secs(&feed(i64::MAX, 16, None), i64::MAX), i64::MAX);
+        assert_eq!(backoff_secs(&feed(HOUR, i64::MAX, None), i64::MAX), HOUR << 16);
+    }
+
+    #[test]
+    fn never_fetched_feeds_are_due() {
+        assert!(is_due(&feed(HOUR, 0, None), now(), DAY));
+    }
+
+    #[test]
+    fn feeds_are_due_after_their_backoff() {
+        assert!(is_due(&feed(HOUR, 0, Some("2024-08-01T11:00:00Z")), now(), DAY));
+        assert!(!is_due(&feed(HOUR, 0, Some("2024-08-01T11:30:00Z")), now(), DAY));
+        assert!(!is_due(&feed(HOUR, 1, Some("2024-08-01T11:00:00Z")), now(), DAY));
+    }
+
+    #[test]
+    fn dead_feeds_are_not_due() {
+        let mut dead = feed(HOUR, 10, None);
+        dead.dead = true;
+        assert!(!is_due(&dead, now(), DAY));
+    }
+
+    #[test]
+    fn out_of_range_intervals_are_not_due() {
+        assert!(!is_due(&feed(i64::MAX, 0, Some("2024-08-01T11:00:00Z")), now(), i64::MAX));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::layout::Layout;
+use crate::article::article_href;
+use crate::breadcrumbs::{BreadCrumbItem, BreadCrumbs};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::FromRow))]
+pub struct SearchResult {
+    pub title: String,
+    // Escaped text with the matches wrapped in <mark>
+    pub snippet: String,
+    pub link: Option<String>,
+    pub feed_id: Option<i64>,
+    pub feed_title: Option<String>,
+}
+
+impl SearchResult {
+    fn href(&self) -> String {
+        match (&self.link, self.feed_id) {
+            (Some(link), Some(feed_id)) => article_href("/article", link, feed_id),
+            (Some(link), None) => link.clone(),
+            (None, Some(feed_id)) => format!("/feeds/{}", feed_id),
+            (None, None) => "/timeline".to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+const MAX_RESULTS: i64 = 50;
+
+// Snippet delimiters that can't occur in feed text, swapped for <mark> after escaping
+#[cfg(feature = "ssr")]
+const MATCH_START: &str = "\u{2}";
+#[cfg(feature = "ssr")]
+const MATCH_END: &str = "\u{3}";
+
+// Every word becomes a quoted phrase, so user input can't produce FTS5 syntax errors
+#[cfg(feature = "ssr")]
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[server]
+pub async fn search(query: String) -> Result<Vec<SearchResult>, ServerFnError> {
+    use crate::db::use_pool;
+    use crate::sanitize::{escape_text, safe_link};
+
+    let fts_query = fts_query(&query);
+    if fts_query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let pool = use_pool()?;
+
+    // bm25 ranks of both indexes are merged, lower is better
+    let results = sqlx::query_as::<_, SearchResult>(
+        "SELECT title, snippet, link, feed_id, feed_title FROM (
+            SELECT
+                COALESCE(items.title, '') AS title,
+                snippet(items_fts, -1, ?1, ?2, '…', 24) AS snippet,
+                items.link AS link,
+                items.feed_id AS feed_id,
+                COALESCE(feeds.custom_title, feeds.title) AS feed_title,
+                items_fts.rank AS rank
+            FROM items_fts
+            JOIN items ON items.id = items_fts.rowid
+            JOIN feeds ON feeds.id = items.feed_id
+            WHERE items_fts MATCH ?3
+            UNION ALL
+            SELECT
+                articles_fts.title AS title,
+                snippet(articles_fts, 2, ?1, ?2, '…', 24) AS snippet,
+                articles_fts.url AS link,
+                items.feed_id AS feed_id,
+                COALESCE(feeds.custom_title, feeds.title) AS feed_title,
+                articles_fts.rank AS rank
+            FROM articles_fts
+            LEFT JOIN items ON items.id = (SELECT id FROM items WHERE link = articles_fts.url LIMIT 1)
+            LEFT JOIN feeds ON feeds.id = items.feed_id
+            WHERE articles_fts MATCH ?3
+        )
+        ORDER BY rank
+        LIMIT ?4",
+    )
+    .bind(MATCH_START)
+    .bind(MATCH_END)
+    .bind(fts_query)
+    .bind(MAX_RESULTS)
+    .fetch_all(&pool)
+    .await?;
+
+    return Ok(results
+        .into_iter()
+        .map(|mut result| {
+            result.snippet = escape_text(&result.snippet)
+                .replace(MATCH_START, "<mark>")
+                .replace(MATCH_END, "</mark>");
+            result.link = result.link.and_then(|link| safe_link(&link));
+            result
+        })
+        .collect());
+}
+
+#[derive(Clone, Params, PartialEq)]
+pub struct SearchQuery {
+    q: Option<String>,
+}
+
+#[component]
+pub fn SearchView() -> impl IntoView {
+    let query = use_query::<SearchQuery>();
+    let q = move || query.with(|query| query.as_ref().ok().and_then(|query| query.q.clone()).unwrap_or_default());
+
+    let results = create_resource(q, |q| async move { search(q).await });
+
+    view! {
+        <Layout headline="Search".to_string()>
+            <BreadCrumbs items=vec![
+                BreadCrumbItem { text: "Feeds".to_string(), url: "/feeds".to_string() },
+                BreadCrumbItem { text: "Search".to_string(), url: "/search".to_string() },
+            ] />
+            <Suspense fallback=|| view! { <p>Searching...</p> }>
+                {move || results.get().map(|results| match results {
+                    Ok(results) if results.is_empty() => view! { <p>No results.</p> }.into_view(),
+                    Ok(results) => results
+                        .into_iter()
+                        .map(|result| view! {
+                            <section class="p-4 my-4 border shadow-lg">
+                                <p class="text-lg">
+                                    <a href=result.href()>{result.title.clone()}</a>
+                                </p>
+                                {result.feed_title.clone().map(|feed_title| view! {
+                                    <p class="text-sm mb-2">{feed_title}</p>
+                                })}
+                                <p inner_html=result.snippet.clone()></p>
+                            </section>
+                        })
+                        .collect_view(),
+                    Err(err) => view! { <p>{err.to_string()}</p> }.into_view(),
+                })}
+            </Suspense>
+        </Layout>
+    }
+}
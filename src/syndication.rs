@@ -0,0 +1,368 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// Format independent representation of a feed document. RSS, RDF, Atom and
+// JSON Feed documents are all parsed into this shape.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Channel {
+    pub title: String,
+    pub description: String,
+    pub link: Option<String>,
+    pub items: Vec<ChannelItem>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChannelItem {
+    pub guid: Option<String>,
+    pub link: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub pub_date: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "ssr")]
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(date.trim())
+        .or_else(|_| DateTime::parse_from_rfc3339(date.trim()))
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+// Name of the document's root element, without namespace prefix
+#[cfg(feature = "ssr")]
+fn root_element(content: &str) -> Option<String> {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    let mut reader = Reader::from_str(content);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                return Some(String::from_utf8_lossy(e.local_name().as_ref()).to_string())
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub fn parse(content: &str) -> Result<Channel, String> {
+    if content.trim_start().starts_with('{') {
+        return parse_json_feed(content);
+    }
+
+    match root_element(content).as_deref() {
+        Some("rss") | Some("RDF") => parse_rss(content),
+        Some("feed") => parse_atom(content),
+        Some(root) => Err(format!("Unsupported feed format: <{}>", root)),
+        None => Err("Document is neither XML nor JSON".to_string()),
+    }
+}
+
+// RSS 0.90, 0.91, 0.92, 1.0 and 2.0. The rss crate also reads RDF documents, where items
+// are siblings of the channel element and dates come from Dublin Core.
+#[cfg(feature = "ssr")]
+fn parse_rss(content: &str) -> Result<Channel, String> {
+    let channel = content
+        .parse::<rss::Channel>()
+        .map_err(|err| err.to_string())?;
+
+    let items = channel
+        .items()
+        .iter()
+        .map(|item| {
+            let dublin_core = item.dublin_core_ext();
+            ChannelItem {
+                guid: item.guid().map(|guid| guid.value().to_string()),
+                author: item
+                    .author()
+                    .map(str::to_string)
+                    .or_else(|| dublin_core.and_then(|dc| dc.creators().first().cloned())),
+                pub_date: item
+                    .pub_date()
+                    .or_else(|| dublin_core.and_then(|dc| dc.dates().first()).map(String::as_str))
+                    .and_then(parse_date),
+                link: item.link().map(str::to_string),
+                title: item.title().map(str::to_string),
+                description: item.description().or(item.content()).map(str::to_string),
+            }
+        })
+        .collect();
+
+    return Ok(Channel {
+        title: channel.title().to_string(),
+        description: channel.description().to_string(),
+        link: Some(channel.link().to_string()),
+        items,
+    });
+}
+
+#[cfg(feature = "ssr")]
+fn parse_atom(content: &str) -> Result<Channel, String> {
+    use atom_syndication::{Feed, Link};
+
+    // Prefer the alternate (HTML) link, which is also the default relation
+    fn alternate_link(links: &[Link]) -> Option<String> {
+        links
+            .iter()
+            .find(|link| link.rel() == "alternate")
+            .or_else(|| links.first())
+            .map(|link| link.href().to_string())
+    }
+
+    let feed = content
+        .parse::<Feed>()
+        .map_err(|err| err.to_string())?;
+
+    let items = feed
+        .entries()
+        .iter()
+        .map(|entry| ChannelItem {
+            guid: Some(entry.id().to_string()),
+            link: alternate_link(entry.links()),
+            title: Some(entry.title().value.clone()),
+            description: entry
+                .summary()
+                .map(|summary| summary.value.clone())
+                .or_else(|| entry.content().and_then(|content| content.value().map(str::to_string))),
+            author: entry.authors().first().map(|author| author.name().to_string()),
+            pub_date: Some(
+                entry
+                    .published()
+                    .unwrap_or(entry.updated())
+                    .with_timezone(&Utc),
+            ),
+        })
+        .collect();
+
+    return Ok(Channel {
+        title: feed.title().value.clone(),
+        description: feed
+            .subtitle()
+            .map(|subtitle| subtitle.value.clone())
+            .unwrap_or_default(),
+        link: alternate_link(feed.links()),
+        items,
+    });
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+struct JsonFeed {
+    title: String,
+    home_page_url: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    items: Vec<JsonFeedItem>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+struct JsonFeedItem {
+    id: Option<serde_json::Value>,
+    url: Option<String>,
+    title: Option<String>,
+    content_html: Option<String>,
+    content_text: Option<String>,
+    summary: Option<String>,
+    date_published: Option<String>,
+    date_modified: Option<String>,
+    // JSON Feed 1.0 has a single author, 1.1 a list of authors
+    author: Option<JsonFeedAuthor>,
+    #[serde(default)]
+    authors: Vec<JsonFeedAuthor>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+struct JsonFeedAuthor {
+    name: Option<String>,
+}
+
+// JSON Feed 1.0 and 1.1
+#[cfg(feature = "ssr")]
+fn parse_json_feed(content: &str) -> Result<Channel, String> {
+    let feed: JsonFeed = serde_json::from_str(content).map_err(|err| err.to_string())?;
+
+    let items = feed
+        .items
+        .into_iter()
+        .map(|item| ChannelItem {
+            guid: item.id.map(|id| match id {
+                serde_json::Value::String(id) => id,
+                id => id.to_string(),
+            }),
+            link: item.url,
+            title: item.title,
+            description: item.summary.or(item.content_html).or(item.content_text),
+            author: item
+                .authors
+                .into_iter()
+                .chain(item.author)
+                .find_map(|author| author.name),
+            pub_date: item
+                .date_published
+                .or(item.date_modified)
+                .as_deref()
+                .and_then(parse_date),
+        })
+        .collect();
+
+    return Ok(Channel {
+        title: feed.title,
+        description: feed.description.unwrap_or_default(),
+        link: feed.home_page_url,
+        items,
+    });
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rss() {
+        let channel = parse(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+              <channel>
+                <title>Example</title>
+                <link>https://example.com/</link>
+                <description>An example feed</description>
+                <item>
+                  <title>First post</title>
+                  <link>https://example.com/first</link>
+                  <guid>first</guid>
+                  <description>Hello</description>
+                  <pubDate>Thu, 23 May 2024 14:20:31 +0000</pubDate>
+                  <dc:creator>Jane Doe</dc:creator>
+                </item>
+              </channel>
+            </rss>"#,
+        )
+        .unwrap();
+
+        assert_eq!(channel.title, "Example");
+        assert_eq!(channel.description, "An example feed");
+        assert_eq!(channel.link.as_deref(), Some("https://example.com/"));
+        assert_eq!(channel.items.len(), 1);
+
+        let item = &channel.items[0];
+        assert_eq!(item.guid.as_deref(), Some("first"));
+        assert_eq!(item.link.as_deref(), Some("https://example.com/first"));
+        assert_eq!(item.title.as_deref(), Some("First post"));
+        assert_eq!(item.description.as_deref(), Some("Hello"));
+        assert_eq!(item.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(item.pub_date, parse_date("2024-05-23T14:20:31Z"));
+    }
+
+    #[test]
+    fn parses_rdf() {
+        let channel = parse(
+            r#"<?xml version="1.0"?>
+            <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                     xmlns="http://purl.org/rss/1.0/"
+                     xmlns:dc="http://purl.org/dc/elements/1.1/">
+              <channel rdf:about="https://example.com/">
+                <title>Example</title>
+                <link>https://example.com/</link>
+                <description>An example feed</description>
+              </channel>
+              <item rdf:about="https://example.com/first">
+                <title>First post</title>
+                <link>https://example.com/first</link>
+                <dc:date>2024-05-23T14:20:31Z</dc:date>
+                <dc:creator>Jane Doe</dc:creator>
+              </item>
+              <item rdf:about="https://example.com/second">
+                <title>Second post</title>
+                <link>https://example.com/second</link>
+              </item>
+            </rdf:RDF>"#,
+        )
+        .unwrap();
+
+        assert_eq!(channel.title, "Example");
+        assert_eq!(channel.items.len(), 2);
+        assert_eq!(channel.items[0].link.as_deref(), Some("https://example.com/first"));
+        assert_eq!(channel.items[0].author.as_deref(), Some("Jane Doe"));
+        assert_eq!(channel.items[0].pub_date, parse_date("2024-05-23T14:20:31Z"));
+        assert_eq!(channel.items[1].title.as_deref(), Some("Second post"));
+        assert_eq!(channel.items[1].pub_date, None);
+    }
+
+    #[test]
+    fn parses_atom() {
+        let channel = parse(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Example</title>
+              <subtitle>An example feed</subtitle>
+              <link rel="self" href="https://example.com/feed.xml"/>
+              <link rel="alternate" href="https://example.com/"/>
+              <id>urn:example</id>
+              <updated>2024-05-24T00:00:00Z</updated>
+              <entry>
+                <title>First post</title>
+                <link href="https://example.com/first"/>
+                <id>urn:example:first</id>
+                <published>2024-05-23T14:20:31Z</published>
+                <updated>2024-05-24T00:00:00Z</updated>
+                <author><name>Jane Doe</name></author>
+                <summary>Hello</summary>
+              </entry>
+            </feed>"#,
+        )
+        .unwrap();
+
+        assert_eq!(channel.title, "Example");
+        assert_eq!(channel.description, "An example feed");
+        assert_eq!(channel.link.as_deref(), Some("https://example.com/"));
+
+        let item = &channel.items[0];
+        assert_eq!(item.guid.as_deref(), Some("urn:example:first"));
+        assert_eq!(item.link.as_deref(), Some("https://example.com/first"));
+        assert_eq!(item.description.as_deref(), Some("Hello"));
+        assert_eq!(item.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(item.pub_date, parse_date("2024-05-23T14:20:31Z"));
+    }
+
+    #[test]
+    fn parses_json_feed() {
+        let channel = parse(
+            r#"{
+              "version": "https://jsonfeed.org/version/1.1",
+              "title": "Example",
+              "home_page_url": "https://example.com/",
+              "items": [
+                {
+                  "id": 1,
+                  "url": "https://example.com/first",
+                  "title": "First post",
+                  "content_html": "<p>Hello</p>",
+                  "date_published": "2024-05-23T14:20:31Z",
+                  "authors": [{ "name": "Jane Doe" }]
+                }
+              ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(channel.title, "Example");
+        assert_eq!(channel.link.as_deref(), Some("https://example.com/"));
+
+        let item = &channel.items[0];
+        assert_eq!(item.guid.as_deref(), Some("1"));
+        assert_eq!(item.description.as_deref(), Some("<p>Hello</p>"));
+        assert_eq!(item.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(item.pub_date, parse_date("2024-05-23T14:20:31Z"));
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(parse("<html><body></body></html>").is_err());
+        assert!(parse("not a feed").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::layout::Layout;
+use crate::breadcrumbs::{BreadCrumbItem, BreadCrumbs};
+use crate::categories::get_categories;
+use crate::feeds::{get_feeds, FeedDetailItem, FeedItem, SetItemStarred};
+use leptos::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+const PAGE_SIZE: i64 = 50;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimelineFilter {
+    pub feed: Option<i64>,
+    pub category: Option<i64>,
+    // Dates as entered in the form, YYYY-MM-DD
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub unread: bool,
+    // Keyset cursor: the sort date and id of the last item of the previous page
+    pub before: Option<String>,
+    pub before_id: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimelinePage {
+    pub items: Vec<FeedItem>,
+    pub next: Option<(String, i64)>,
+}
+
+#[server]
+pub async fn get_timeline(filter: TimelineFilter) -> Result<TimelinePage, ServerFnError> {
+    use crate::config::use_config;
+    use crate::db::use_pool;
+    use crate::feeds::sanitize_item;
+    use sqlx::{FromRow, QueryBuilder, Row, Sqlite};
+
+    let config = use_config()?;
+    let pool = use_pool()?;
+
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM items WHERE 1 = 1");
+    if let Some(feed) = filter.feed {
+        query.push(" AND feed_id = ").push_bind(feed);
+    }
+    if let Some(category) = filter.category {
+        // Subcategories belong to their parent
+        query
+            .push(
+                " AND feed_id IN (SELECT feed_id FROM feed_categories WHERE category_id IN (
+                    WITH RECURSIVE tree(id) AS (
+                        SELECT ",
+            )
+            .push_bind(category)
+            .push(
+                " UNION ALL SELECT categories.id FROM categories JOIN tree ON categories.parent_id = tree.id
+                    )
+                    SELECT id FROM tree))",
+            );
+    }
+    if let Some(since) = filter.since.filter(|since| !since.is_empty()) {
+        query.push(" AND sort_date >= ").push_bind(since);
+    }
+    if let Some(until) = filter.until.filter(|until| !until.is_empty()) {
+        query.push(" AND sort_date < date(").push_bind(until).push(", '+1 day')");
+    }
+    if filter.unread {
+        query.push(" AND read = 0");
+    }
+    if let (Some(before), Some(before_id)) = (filter.before, filter.before_id) {
+        query
+            .push(" AND (sort_date, id) < (")
+            .push_bind(before)
+            .push(", ")
+            .push_bind(before_id)
+            .push(")");
+    }
+    // One extra row tells us whether there is another page
+    query
+        .push(" ORDER BY sort_date DESC, id DESC LIMIT ")
+        .push_bind(PAGE_SIZE + 1);
+
+    let rows = query.build().fetch_all(&pool).await?;
+
+    let mut items = Vec::new();
+    for row in rows.iter().take(PAGE_SIZE as usize) {
+        items.push(sanitize_item(FeedItem::from_row(row)?, config.allow_iframes));
+    }
+
+    let next = match rows.get(PAGE_SIZE as usize - 1) {
+        Some(last) if rows.len() as i64 > PAGE_SIZE => {
+            Some((last.try_get::<String, _>("sort_date")?, last.try_get::<i64, _>("id")?))
+        }
+        _ => None,
+    };
+
+    return Ok(TimelinePage { items, next });
+}
+
+#[derive(Clone, Params, PartialEq)]
+pub struct TimelineQuery {
+    // "All" submits an empty value, which wouldn't parse as a number
+    feed: Option<String>,
+    category: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    // Checkboxes submit "on" when ticked and nothing otherwise
+    unread: Option<String>,
+    before: Option<String>,
+    before_id: Option<i64>,
+}
+
+impl TimelineQuery {
+    fn filter(&self) -> TimelineFilter {
+        TimelineFilter {
+            feed: self.feed.as_deref().and_then(|feed| feed.parse().ok()),
+            category: self.category.as_deref().and_then(|category| category.parse().ok()),
+            since: self.since.clone(),
+            until: self.until.clone(),
+            unread: self.unread.is_some(),
+            before: self.before.clone(),
+            before_id: self.before_id,
+        }
+    }
+}
+
+fn timeline_url(filter: &TimelineFilter) -> String {
+    let mut params = url::form_urlencoded::Serializer::new(String::new());
+    if let Some(feed) = filter.feed {
+        params.append_pair("feed", &feed.to_string());
+    }
+    if let Some(category) = filter.category {
+        params.append_pair("category", &category.to_string());
+    }
+    if let Some(since) = &filter.since {
+        params.append_pair("since", since);
+    }
+    if let Some(until) = &filter.until {
+        params.append_pair("until", until);
+    }
+    if filter.unread {
+        params.append_pair("unread", "on");
+    }
+    if let (Some(before), Some(before_id)) = (&filter.before, filter.before_id) {
+        params.append_pair("before", before);
+        params.append_pair("before_id", &before_id.to_string());
+    }
+
+    return format!("/timeline?{}", params.finish());
+}
+
+#[component]
+pub fn TimelineView() -> impl IntoView {
+    let query = use_query::<TimelineQuery>();
+    let filter = move || query.get().map(|q| q.filter()).unwrap_or_default();
+
+    let set_starred = create_server_action::<SetItemStarred>();
+
+    provide_context(set_starred);
+
+    let options = create_resource(
+        || (),
+        |_| async move {
+            let feeds = get_feeds().await.unwrap_or_default();
+            let categories = get_categories().await.unwrap_or_default();
+            (feeds, categories)
+        },
+    );
+
+    let feed_title = move |id: i64| {
+        options.with(|options| {
+            options
+                .as_ref()
+                .and_then(|(feeds, _)| feeds.iter().find(|feed| feed.id == id))
+                .map(|feed| feed.display_title())
+        })
+    };
+
+    let page = create_resource(
+        move || (filter(), set_starred.version().get()),
+        |(filter, _)| async move { get_timeline(filter).await },
+    );
+
+    view! {
+        <Layout headline="Timeline".to_string()>
+            <BreadCrumbs items=vec![
+                BreadCrumbItem { text: "Feeds".to_string(), url: "/feeds".to_string() },
+                BreadCrumbItem { text: "Timeline".to_string(), url: "/timeline".to_string() },
+            ] />
+            <Suspense fallback=|| view! { <p>Loading...</p> }>
+                {move || options.get().map(|(feeds, categories)| {
+                    let current = filter();
+                    view! {
+                        <Form method="GET" action="/timeline" class="flex flex-wrap items-center gap-2 my-4">
+                            <select class="p-2 rounded border" name="feed">
+                                <option value="">All feeds</option>
+                                {feeds
+                                    .into_iter()
+                                    .map(|feed| view! {
+                                        <option value=feed.id.to_string() selected=current.feed == Some(feed.id)>{feed.display_title()}</option>
+                                    })
+                                    .collect_view()}
+                            </select>
+                            <select class="p-2 rounded border" name="category">
+                                <option value="">All categories</option>
+                                {categories
+                                    .into_iter()
+                                    .map(|category| view! {
+                                        <option value=category.id.to_string() selected=current.category == Some(category.id)>{category.name}</option>
+                                    })
+                                    .collect_view()}
+                            </select>
+                            <input class="p-2 rounded border" type="date" name="since" value=current.since.clone().unwrap_or_default() />
+                            <input class="p-2 rounded border" type="date" name="until" value=current.until.clone().unwrap_or_default() />
+                            <label class="flex items-center gap-2">
+                                <input type="checkbox" name="unread" checked=current.unread />
+                                "Unread only"
+                            </label>
+                            <button class="p-2 rounded bg-slate-100" type="submit">Filter</button>
+                        </Form>
+                    }
+                })}
+            </Suspense>
+            <Suspense fallback=|| view! { <p>Loading...</p> }>
+                {move || page.get().map(|page| match page {
+                    Ok(page) if page.items.is_empty() => view! { <p>No items.</p> }.into_view(),
+                    Ok(page) => {
+                        let items = page.items;
+                        let mut current = filter();
+                        let newest_url = timeline_url(&TimelineFilter { before: None, before_id: None, ..current.clone() });
+                        let older_url = page.next.map(|(before, before_id)| {
+                            current.before = Some(before);
+                            current.before_id = Some(before_id);
+                            timeline_url(&current)
+                        });
+                        view! {
+                            <For
+                                each=move || items.clone()
+                                key=|item| item.id
+                                children=move |item| {
+                                    let feed_id = item.feed_id;
+                                    let feed_title = feed_title(feed_id);
+                                    view! { <FeedDetailItem item feed_id feed_title /> }
+                                }
+                            />
+                            <p class="my-4 flex gap-4">
+                                <Show when=move || filter().before.is_some()>
+                                    <a href=newest_url.clone()>Newest</a>
+                                </Show>
+                                {older_url.map(|url| view! { <a href=url>Older</a> })}
+                            </p>
+                        }.into_view()
+                    }
+                    Err(err) => view! { <p>{err.to_string()}</p> }.into_view(),
+                })}
+            </Suspense>
+        </Layout>
+    }
+}